@@ -3,7 +3,7 @@ use alloc::string::String;
 use alloc::vec::Vec;
 use core::str;
 
-pub fn shell(_: &mut u8) {
+pub fn shell() {
     let mut stdin = fe_osi::ipc::Subscriber::new("stdin").unwrap();
     let mut stdout = fe_osi::ipc::Publisher::new("stdout").unwrap();
 
@@ -24,8 +24,12 @@ pub fn shell(_: &mut u8) {
         match cmd_buffer.iter().position(|&x| x == '\r' as u8) {
             Some(index) => {
                 let command: Vec<u8> = cmd_buffer.drain(..index + 1).collect();
-                if str::from_utf8(&command).unwrap() == "hello\r" {
-                    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, hello_world, None);
+                match str::from_utf8(&command).unwrap() {
+                    "hello\r" => {
+                        fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, hello_world);
+                    }
+                    "ps\r" => ps(&mut stdout),
+                    _ => (),
                 }
 
                 match stdout.publish(prompt.clone().into_bytes()) {
@@ -38,7 +42,7 @@ pub fn shell(_: &mut u8) {
     }
 }
 
-pub fn hello_world(_: &mut u8) {
+pub fn hello_world() {
     let mut stdout = fe_osi::ipc::Publisher::new("stdout").unwrap();
     let mut count: usize = 0;
     loop {
@@ -48,3 +52,29 @@ pub fn hello_world(_: &mut u8) {
         count += 1;
     }
 }
+
+fn ps(stdout: &mut fe_osi::ipc::Publisher) {
+    let mut output = String::from("\r\nPID NAME       STATE     PRIORITY STACK USED  CPU\r\n");
+    for task in fe_osi::task::task_list() {
+        let name = task.name.as_deref().unwrap_or("-");
+        let state = format!("{:?}", task.state);
+        output += &format!(
+            "{:<3} {:<10} {:<9} {:<8} {:<5} {:<5} {}%\r\n",
+            task.pid,
+            name,
+            state,
+            task.priority,
+            task.stack_size,
+            task.stack_high_water,
+            task.cpu_usage.percent
+        );
+    }
+    if let Some(idle) = fe_osi::task::cpu_usage(fe_osi::task::IDLE_PID) {
+        output += &format!("Idle: {}%\r\n", idle.percent);
+    }
+
+    match stdout.publish(output.into_bytes()) {
+        Ok(_) => (),
+        Err(_) => panic!("Error publishing"),
+    }
+}
@@ -8,7 +8,7 @@ mod uart_server;
 #[macro_use]
 extern crate alloc;
 
-use alloc::boxed::Box;
+use fe_rtos::task::TickConfig;
 use hal::prelude::*;
 #[cfg(feature = "tm4c123")]
 use tm4c123x_hal as hal;
@@ -64,23 +64,19 @@ fn main() -> ! {
 
     fe_rtos::arch::arch_setup(&mut cp);
 
-    let (uart0_tx, uart0_rx) = uart0.split();
+    let (mut uart0_tx, mut uart0_rx) = uart0.split();
 
-    fe_osi::task::task_spawn(
-        fe_rtos::task::DEFAULT_STACK_SIZE,
-        uart_server::uart_transmit_server,
-        Some(Box::new(uart0_tx)),
-    );
+    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, move || {
+        uart_server::uart_transmit_server(&mut uart0_tx)
+    });
 
-    fe_osi::task::task_spawn(
-        fe_rtos::task::DEFAULT_STACK_SIZE,
-        uart_server::uart_receive_server,
-        Some(Box::new(uart0_rx)),
-    );
+    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, move || {
+        uart_server::uart_receive_server(&mut uart0_rx)
+    });
 
-    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, stdio::stdout, None);
-    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, stdio::stdin, None);
-    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, cmd::shell, None);
+    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, stdio::stdout);
+    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, stdio::stdin);
+    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, cmd::shell);
 
     //It's probably a good idea to have the context switch be the lowest
     //priority interrupt.
@@ -89,15 +85,8 @@ fn main() -> ! {
             .set_priority(cortex_m::peripheral::scb::SystemHandler::PendSV, 7);
     }
 
-    //Start the FeRTOS scheduler
-    let enable_systick = |reload: usize| {
-        cp.SYST.set_reload(reload as u32);
-        cp.SYST.clear_current();
-        cp.SYST.enable_counter();
-        cp.SYST.enable_interrupt();
-    };
-    let reload_val = cortex_m::peripheral::SYST::get_ticks_per_10ms() / 10;
-    fe_rtos::task::start_scheduler(enable_systick, reload_val as usize);
+    //Start the FeRTOS scheduler with a 1ms tick
+    fe_rtos::task::start_scheduler(TickConfig::new(clocks.sysclk.0 as usize, 1000));
 
     loop {}
 }
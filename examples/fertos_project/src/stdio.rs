@@ -1,4 +1,4 @@
-pub fn stdout(_: &mut u8) {
+pub fn stdout() {
     let mut std_out = fe_osi::ipc::Subscriber::new("stdout").unwrap();
     let mut uart_tx = fe_osi::ipc::Publisher::new("uart_tx").unwrap();
     loop {
@@ -8,7 +8,7 @@ pub fn stdout(_: &mut u8) {
     }
 }
 
-pub fn stdin(_: &mut u8) {
+pub fn stdin() {
     let mut uart_rx = fe_osi::ipc::Subscriber::new("uart_rx").unwrap();
     let mut std_in = fe_osi::ipc::Publisher::new("stdin").unwrap();
     loop {
@@ -6,6 +6,10 @@ extern crate alloc;
 use alloc::boxed::Box;
 use alloc::sync::Arc;
 use fe_osi::allocator::get_heap_remaining;
+use fe_rtos::task::TickConfig;
+
+//The core clock frequency of QEMU's lm3s6965evb
+const CORE_CLOCK_HZ: usize = 12_000_000;
 
 fn write_byte(c: u8) {
     let uart0: *mut usize = 0x4000_C000 as *mut usize;
@@ -14,7 +18,7 @@ fn write_byte(c: u8) {
     }
 }
 
-fn hello_task(_: &mut usize) {
+fn hello_task() {
     let mut stdout = fe_osi::ipc::Publisher::new("stdout").unwrap();
     let mut counter = 0;
     loop {
@@ -27,7 +31,7 @@ fn hello_task(_: &mut usize) {
     }
 }
 
-fn writer_task(_: &mut usize) {
+fn writer_task() {
     let mut subscriber = fe_osi::ipc::Subscriber::new("stdout").unwrap();
     loop {
         if let Some(msg) = subscriber.get_message_nonblocking() {
@@ -38,7 +42,7 @@ fn writer_task(_: &mut usize) {
     }
 }
 
-fn test_task(_: &mut usize) {
+fn test_task() {
     {
         let _test: Arc<[u32]> = Arc::new([0; 200]);
         let _test2: Box<[u32]> = Box::new([0; 200]);
@@ -51,9 +55,11 @@ fn test_task(_: &mut usize) {
     loop {}
 }
 
-fn spawn_task(_: &mut usize) {
+fn spawn_task() {
     loop {
-        fe_osi::task::task_spawn_block(fe_rtos::task::DEFAULT_STACK_SIZE, test_task, None);
+        fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, test_task)
+            .join()
+            .ok();
         fe_osi::sleep(1000);
     }
 }
@@ -64,23 +70,17 @@ fn main() -> ! {
 
     fe_rtos::arch::arch_setup(&mut p);
 
-    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, hello_task, None);
-    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, writer_task, None);
-    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, spawn_task, None);
+    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, hello_task);
+    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, writer_task);
+    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, spawn_task);
 
     fe_osi::set_putc(|c: char| {
         write_byte(c as u8);
     });
 
-    //Start the FeRTOS scheduler
-    let enable_systick = |reload: usize| {
-        p.SYST.set_reload(reload as u32);
-        p.SYST.clear_current();
-        p.SYST.enable_counter();
-        p.SYST.enable_interrupt();
-    };
-    let reload_val = cortex_m::peripheral::SYST::get_ticks_per_10ms() / 10;
-    fe_rtos::task::start_scheduler(enable_systick, reload_val as usize);
+    //Start the FeRTOS scheduler with a 1ms tick
+    //SysTick is set to count the core clock
+    fe_rtos::task::start_scheduler(TickConfig::new(CORE_CLOCK_HZ, 1000));
 
     loop {}
 }
@@ -6,6 +6,7 @@ extern crate alloc;
 use alloc::boxed::Box;
 use alloc::sync::Arc;
 use fe_osi::allocator::get_heap_remaining;
+use fe_rtos::task::TickConfig;
 
 fn write_byte(c: u8) {
     let uart0: *mut usize = 0x1000_0000 as *mut usize;
@@ -14,7 +15,7 @@ fn write_byte(c: u8) {
     }
 }
 
-fn hello_task(_: &mut usize) {
+fn hello_task() {
     let mut stdout = fe_osi::ipc::Publisher::new("stdout").unwrap();
     let mut counter = 0;
     loop {
@@ -26,7 +27,7 @@ fn hello_task(_: &mut usize) {
     }
 }
 
-fn writer_task(_: &mut usize) {
+fn writer_task() {
     let mut subscriber = fe_osi::ipc::Subscriber::new("stdout").unwrap();
     loop {
         if let Some(msg) = subscriber.get_message_nonblocking() {
@@ -39,7 +40,7 @@ fn writer_task(_: &mut usize) {
     }
 }
 
-fn test_task(_: &mut usize) {
+fn test_task() {
     {
         let _test: Arc<[u32]> = Arc::new([0; 200]);
         let _test2: Box<[u32]> = Box::new([0; 200]);
@@ -52,9 +53,11 @@ fn test_task(_: &mut usize) {
     loop {}
 }
 
-fn spawn_task(_: &mut usize) {
+fn spawn_task() {
     loop {
-        fe_osi::task::task_spawn_block(fe_rtos::task::DEFAULT_STACK_SIZE, test_task, None);
+        fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, test_task)
+            .join()
+            .ok();
         fe_osi::sleep(100);
     }
 }
@@ -73,14 +76,13 @@ fn main() -> ! {
         write_byte(c as u8);
     });
 
-    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, hello_task, None);
-    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, writer_task, None);
-    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, spawn_task, None);
+    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, hello_task);
+    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, writer_task);
+    fe_osi::task::task_spawn(fe_rtos::task::DEFAULT_STACK_SIZE, spawn_task);
 
     //Start the FeRTOS scheduler
     //Systick every 10ms
-    let reload_val = TIMEBASE_FREQ / 100;
-    fe_rtos::task::start_scheduler(fe_rtos::arch::enable_systick, reload_val);
+    fe_rtos::task::start_scheduler(TickConfig::new(TIMEBASE_FREQ, 100));
 
     loop {}
 }
@@ -13,6 +13,29 @@
     .global ipc_get_message
     .global do_get_heap_remaining
     .global do_register_interrupt
+    .global do_task_resume
+    .global do_task_deadline_misses
+    .global do_mutex_lock
+    .global do_mutex_unlock
+    .global do_sem_take
+    .global do_task_list
+    .global do_task_stack_high_water
+    .global do_task_suspend
+    .global do_task_kill
+    .global do_task_cpu_usage
+    .global do_sem_wake
+    .global do_event_wake
+    .global do_cond_wake
+    .global do_timer_create
+    .global do_timer_start
+    .global do_timer_stop
+    .global do_timer_change_period
+    .global do_timer_delete
+    .global do_sleep_until
+    .global do_get_ticks
+    .global do_get_tick_hz
+    .global do_event_wait
+    .global do_cond_wait
 
     .thumb_func
 do_exit:
@@ -91,3 +114,137 @@ do_register_interrupt:
     PUSH { LR }
     svc 0xc
     POP { PC }
+
+    .thumb_func
+do_task_resume:
+    PUSH { LR }
+    svc 0xd
+    POP { PC }
+
+    .thumb_func
+do_task_deadline_misses:
+    PUSH { LR }
+    svc 0xe
+    POP { PC }
+
+    .thumb_func
+do_mutex_lock:
+    PUSH { LR }
+    svc 0xf
+    POP { PC }
+
+    .thumb_func
+do_mutex_unlock:
+    PUSH { LR }
+    svc 0x10
+    POP { PC }
+
+    .thumb_func
+do_sem_take:
+    PUSH { LR }
+    svc 0x11
+    POP { PC }
+
+    .thumb_func
+do_task_list:
+    PUSH { LR }
+    svc 0x12
+    POP { PC }
+
+    .thumb_func
+do_task_stack_high_water:
+    PUSH { LR }
+    svc 0x13
+    POP { PC }
+
+    .thumb_func
+do_task_suspend:
+    PUSH { LR }
+    svc 0x14
+    POP { PC }
+
+    .thumb_func
+do_task_kill:
+    PUSH { LR }
+    svc 0x15
+    POP { PC }
+
+    .thumb_func
+do_task_cpu_usage:
+    PUSH { LR }
+    svc 0x16
+    POP { PC }
+
+    //Semaphores can be given from interrupt handlers, where svc can't be
+    //used, so this calls into the kernel directly
+    .thumb_func
+do_sem_wake:
+    B sys_sem_wake
+
+    .thumb_func
+do_event_wake:
+    B sys_event_wake
+
+    .thumb_func
+do_cond_wake:
+    B sys_cond_wake
+
+    .thumb_func
+do_timer_create:
+    PUSH { LR }
+    svc 0x17
+    POP { PC }
+
+    .thumb_func
+do_timer_start:
+    PUSH { LR }
+    svc 0x18
+    POP { PC }
+
+    .thumb_func
+do_timer_stop:
+    PUSH { LR }
+    svc 0x19
+    POP { PC }
+
+    .thumb_func
+do_timer_change_period:
+    PUSH { LR }
+    svc 0x1a
+    POP { PC }
+
+    .thumb_func
+do_timer_delete:
+    PUSH { LR }
+    svc 0x1b
+    POP { PC }
+
+    .thumb_func
+do_sleep_until:
+    PUSH { LR }
+    svc 0x1c
+    POP { PC }
+
+    .thumb_func
+do_get_ticks:
+    PUSH { LR }
+    svc 0x1d
+    POP { PC }
+
+    .thumb_func
+do_get_tick_hz:
+    PUSH { LR }
+    svc 0x1e
+    POP { PC }
+
+    .thumb_func
+do_event_wait:
+    PUSH { LR }
+    svc 0x1f
+    POP { PC }
+
+    .thumb_func
+do_cond_wait:
+    PUSH { LR }
+    svc 0x20
+    POP { PC }
@@ -7,13 +7,20 @@ pub mod allocator;
 pub mod interrupt;
 pub mod ipc;
 pub mod semaphore;
+pub mod sync;
 pub mod task;
+pub mod time;
+pub mod timer;
+
+/// A timeout that never expires.
+pub const WAIT_FOREVER: u32 = u32::MAX;
 
 static mut PUTC: Option<fn(char)> = None;
 
 extern "C" {
     fn do_exit() -> usize;
     fn do_sleep(seconds: u32) -> usize;
+    fn do_sleep_until(tick: u64) -> usize;
     fn do_yield() -> usize;
 }
 
@@ -63,6 +70,15 @@ pub fn sleep(ms: u32) -> usize {
     0
 }
 
+/// Causes the calling task to block until the tick count reaches `tick`.
+/// Returns false without blocking if it has already passed `tick`.
+///
+/// Sleeping until an absolute tick keeps a periodic loop from drifting by
+/// however long its work takes. See `task::Periodic`.
+pub fn sleep_until(tick: u64) -> bool {
+    unsafe { do_sleep_until(tick) == 0 }
+}
+
 /// Triggers a context switch.
 pub fn r#yield() -> usize {
     unsafe {
@@ -15,6 +15,29 @@
 .globl ipc_get_message
 .globl do_get_heap_remaining
 .globl do_register_interrupt
+.globl do_task_resume
+.globl do_task_deadline_misses
+.globl do_mutex_lock
+.globl do_mutex_unlock
+.globl do_sem_take
+.globl do_task_list
+.globl do_task_stack_high_water
+.globl do_task_suspend
+.globl do_task_kill
+.globl do_task_cpu_usage
+.globl do_sem_wake
+.globl do_event_wake
+.globl do_cond_wake
+.globl do_timer_create
+.globl do_timer_start
+.globl do_timer_stop
+.globl do_timer_change_period
+.globl do_timer_delete
+.globl do_sleep_until
+.globl do_get_ticks
+.globl do_get_tick_hz
+.globl do_event_wait
+.globl do_cond_wait
 
 do_exit:
     addi sp, sp, -16
@@ -198,4 +221,295 @@ do_register_interrupt:
     addi sp, sp, 16
     ret
 
+do_task_resume:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0xd
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_task_deadline_misses:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0xe
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_mutex_lock:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0xf
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_mutex_unlock:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x10
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_sem_take:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x11
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_task_list:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x12
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_task_stack_high_water:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x13
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_task_suspend:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x14
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_task_kill:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x15
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_task_cpu_usage:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x16
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+#Semaphores can be given from interrupt handlers, where ecall can't be
+#used, so this calls into the kernel directly
+do_sem_wake:
+    tail sys_sem_wake
+
+do_event_wake:
+    tail sys_event_wake
+
+do_cond_wake:
+    tail sys_cond_wake
+
+do_timer_create:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x17
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_timer_start:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x18
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_timer_stop:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x19
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_timer_change_period:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x1a
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_timer_delete:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x1b
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_sleep_until:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x1c
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_get_ticks:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x1d
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_get_tick_hz:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x1e
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_event_wait:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x1f
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
+do_cond_wait:
+    addi sp, sp, -16
+    sw ra, 12(sp)
+    sw fp, 8(sp)
+    addi fp, sp, 16
+
+    li a7, 0x20
+    ecall
+
+    lw fp, 8(sp)
+    lw ra, 12(sp)
+    addi sp, sp, 16
+    ret
+
 .option pop
@@ -5,11 +5,18 @@ use core::sync::atomic::{AtomicUsize, Ordering};
 pub struct Semaphore {
     count: AtomicUsize,
     mutex: bool,
+    //The first task waiting for the Semaphore. This is managed by the kernel.
+    waiters: AtomicUsize,
 }
 
 extern "C" {
     //System call to block until a semaphore is available
     fn do_block(sem: *const Semaphore) -> usize;
+    //System call to take a semaphore, blocking until a timeout
+    fn do_sem_take(sem: *const Semaphore, timeout: u32) -> usize;
+    //Wakes up the first task waiting for a semaphore. Unlike the system calls,
+    //this can be called from an interrupt handler.
+    fn do_sem_wake(sem: *const Semaphore) -> usize;
 }
 
 impl Semaphore {
@@ -18,6 +25,7 @@ impl Semaphore {
         Semaphore {
             count: AtomicUsize::new(start_count),
             mutex: false,
+            waiters: AtomicUsize::new(0),
         }
     }
 
@@ -26,6 +34,7 @@ impl Semaphore {
         Semaphore {
             count: AtomicUsize::new(1),
             mutex: true,
+            waiters: AtomicUsize::new(0),
         }
     }
 
@@ -58,6 +67,16 @@ impl Semaphore {
         }
     }
 
+    /// Takes a Semaphore for a task, blocking for at most `ms` milliseconds.
+    /// Returns true if the Semaphore was taken, false if the timeout expired.
+    pub fn take_timeout(&self, ms: u32) -> bool {
+        if self.try_take() {
+            return true;
+        }
+
+        unsafe { do_sem_take(self as *const Semaphore, ms) == 0 }
+    }
+
     /// Attempt to take the Semaphore. If the Semaphore is taken, return true.
     /// Otherwise, return false.
     pub fn try_take(&self) -> bool {
@@ -74,12 +93,27 @@ impl Semaphore {
 
     /// In a mutex Semaphore, give() sets the count to 1.
     /// In a non-mutex Semaphore, gives increments the count by 1.
+    /// If a task is waiting for the Semaphore, it is woken up.
+    /// This can be called from an interrupt handler.
     pub fn give(&self) {
         if self.mutex {
             self.count.store(1, Ordering::SeqCst);
         } else {
             self.count.fetch_add(1, Ordering::SeqCst);
         }
+
+        if self.waiters.load(Ordering::SeqCst) != 0 {
+            unsafe {
+                do_sem_wake(self as *const Semaphore);
+            }
+        }
+    }
+
+    /// Returns the head of the queue of tasks waiting for the Semaphore.
+    /// This is used by the kernel to wake up waiting tasks.
+    #[doc(hidden)]
+    pub fn raw_waiters(&self) -> &AtomicUsize {
+        &self.waiters
     }
 
     /// Takes the Semaphore, executes the closure, then gives the Semaphore.
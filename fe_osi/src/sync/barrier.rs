@@ -0,0 +1,84 @@
+use crate::sync::{Condvar, Mutex};
+
+/// Lets a number of tasks wait until all of them have reached the same point.
+///
+/// Waiting tasks block in the kernel, so they don't use any CPU time.
+/// Once every task has arrived, they are all released and the Barrier can be
+/// used again.
+///
+/// #Examples
+/// ```
+/// static ARMED: Barrier = Barrier::new(3);
+///
+/// //Each of the three sensor tasks
+/// calibrate();
+/// if ARMED.wait().is_leader() {
+///     print_msg("Sensors armed\r\n");
+/// }
+/// sample();
+/// ```
+pub struct Barrier {
+    state: Mutex<BarrierState>,
+    //Notified when the last task arrives
+    all_arrived: Condvar,
+    num_tasks: usize,
+}
+
+struct BarrierState {
+    //The number of tasks that have arrived since the Barrier was last released
+    arrived: usize,
+    //Counts how many times the Barrier has been released, so that waiting
+    //tasks can tell they were released apart from being woken up spuriously
+    generation: usize,
+}
+
+/// Returned by `Barrier::wait` to tell one of the tasks it was the leader.
+#[derive(Debug)]
+pub struct BarrierWaitResult {
+    is_leader: bool,
+}
+
+impl BarrierWaitResult {
+    /// Returns true for the one task of each group released by a Barrier that
+    /// arrived last.
+    pub fn is_leader(&self) -> bool {
+        self.is_leader
+    }
+}
+
+impl Barrier {
+    /// Creates a Barrier that releases waiting tasks once `num_tasks` have
+    /// called `wait`. A Barrier for 0 tasks behaves like one for 1 task.
+    pub const fn new(num_tasks: usize) -> Barrier {
+        Barrier {
+            state: Mutex::new(BarrierState {
+                arrived: 0,
+                generation: 0,
+            }),
+            all_arrived: Condvar::new(),
+            num_tasks,
+        }
+    }
+
+    /// Blocks until every task has called `wait`. The last task to call it
+    /// doesn't block and is the leader.
+    pub fn wait(&self) -> BarrierWaitResult {
+        let mut state = self.state.lock();
+        let generation = state.generation;
+        state.arrived += 1;
+
+        if state.arrived < self.num_tasks {
+            while generation == state.generation {
+                state = self.all_arrived.wait(state);
+            }
+
+            BarrierWaitResult { is_leader: false }
+        } else {
+            state.arrived = 0;
+            state.generation = state.generation.wrapping_add(1);
+            self.all_arrived.notify_all();
+
+            BarrierWaitResult { is_leader: true }
+        }
+    }
+}
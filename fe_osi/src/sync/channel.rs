@@ -0,0 +1,293 @@
+extern crate alloc;
+
+use crate::sync::{remaining, Condvar, Mutex, MutexGuard};
+use crate::time;
+use crate::WAIT_FOREVER;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+/// Creates a channel that holds at most `capacity` values, returning the
+/// sending and receiving halves of it. A capacity of 0 is treated as 1.
+///
+/// Either half can be cloned, so any number of tasks can send and receive.
+/// Each value is received by only one Receiver. Once the channel is full,
+/// senders block until a value is received.
+///
+/// #Examples
+/// ```
+/// let (tx, rx) = channel::<Reading>(8);
+///
+/// //Sensor task
+/// tx.send(Reading { id: 0, value: 42 }).ok();
+///
+/// //Logging task
+/// while let Some(reading) = rx.recv() {
+///     log(reading);
+/// }
+/// ```
+pub fn channel<T: Send>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let channel = Arc::new(Channel {
+        state: Mutex::new(ChannelState {
+            queue: VecDeque::with_capacity(capacity.max(1)),
+            senders: 1,
+            receivers: 1,
+        }),
+        not_empty: Condvar::new(),
+        not_full: Condvar::new(),
+        capacity: capacity.max(1),
+    });
+
+    (
+        Sender {
+            channel: channel.clone(),
+        },
+        Receiver { channel },
+    )
+}
+
+struct Channel<T> {
+    state: Mutex<ChannelState<T>>,
+    //Notified when a value is sent or the last Sender is dropped
+    not_empty: Condvar,
+    //Notified when a value is received or the last Receiver is dropped
+    not_full: Condvar,
+    capacity: usize,
+}
+
+struct ChannelState<T> {
+    queue: VecDeque<T>,
+    senders: usize,
+    receivers: usize,
+}
+
+/// The sending half of a channel.
+pub struct Sender<T> {
+    channel: Arc<Channel<T>>,
+}
+
+/// The receiving half of a channel.
+pub struct Receiver<T> {
+    channel: Arc<Channel<T>>,
+}
+
+/// Why `Sender::try_send` failed. The value that wasn't sent is given back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The channel is full.
+    Full(T),
+    /// Every Receiver has been dropped.
+    Disconnected(T),
+}
+
+/// Why `Sender::send_timeout` failed. The value that wasn't sent is given back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SendTimeoutError<T> {
+    /// The channel stayed full until the timeout expired.
+    Timeout(T),
+    /// Every Receiver has been dropped.
+    Disconnected(T),
+}
+
+/// Why `Receiver::try_recv` failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// The channel is empty.
+    Empty,
+    /// The channel is empty and every Sender has been dropped.
+    Disconnected,
+}
+
+/// Why `Receiver::recv_timeout` failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+    /// The channel stayed empty until the timeout expired.
+    Timeout,
+    /// The channel is empty and every Sender has been dropped.
+    Disconnected,
+}
+
+impl<T> TrySendError<T> {
+    /// Returns the value that wasn't sent.
+    pub fn into_inner(self) -> T {
+        match self {
+            TrySendError::Full(value) | TrySendError::Disconnected(value) => value,
+        }
+    }
+}
+
+impl<T> SendTimeoutError<T> {
+    /// Returns the value that wasn't sent.
+    pub fn into_inner(self) -> T {
+        match self {
+            SendTimeoutError::Timeout(value) | SendTimeoutError::Disconnected(value) => value,
+        }
+    }
+}
+
+impl<T> Channel<T> {
+    fn send(&self, value: T, ms: u32) -> Result<(), SendTimeoutError<T>> {
+        let start = time::ticks();
+        let mut state = self.state.lock();
+
+        loop {
+            if state.receivers == 0 {
+                return Err(SendTimeoutError::Disconnected(value));
+            }
+
+            if state.queue.len() < self.capacity {
+                state.queue.push_back(value);
+                self.not_empty.notify_one();
+                return Ok(());
+            }
+
+            state = match self.wait(&self.not_full, state, start, ms) {
+                Some(state) => state,
+                None => return Err(SendTimeoutError::Timeout(value)),
+            };
+        }
+    }
+
+    fn recv(&self, ms: u32) -> Result<T, RecvTimeoutError> {
+        let start = time::ticks();
+        let mut state = self.state.lock();
+
+        loop {
+            if let Some(value) = state.queue.pop_front() {
+                self.not_full.notify_one();
+                return Ok(value);
+            }
+
+            if state.senders == 0 {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+
+            state = self
+                .wait(&self.not_empty, state, start, ms)
+                .ok_or(RecvTimeoutError::Timeout)?;
+        }
+    }
+
+    //Waits on the Condvar for what's left of the timeout.
+    //Returns None if there is no time left.
+    fn wait<'a>(
+        &self,
+        condvar: &Condvar,
+        state: MutexGuard<'a, ChannelState<T>>,
+        start: u64,
+        ms: u32,
+    ) -> Option<MutexGuard<'a, ChannelState<T>>> {
+        match remaining(start, ms) {
+            0 => None,
+            left => Some(condvar.wait_timeout(state, left).0),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    /// Sends a value, blocking while the channel is full.
+    /// If every Receiver has been dropped, the value is given back.
+    pub fn send(&self, value: T) -> Result<(), T> {
+        self.channel
+            .send(value, WAIT_FOREVER)
+            .map_err(SendTimeoutError::into_inner)
+    }
+
+    /// Sends a value if the channel isn't full, without blocking.
+    /// If the channel is full or every Receiver has been dropped, the value
+    /// is given back in the error.
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.channel.send(value, 0).map_err(|err| match err {
+            SendTimeoutError::Timeout(value) => TrySendError::Full(value),
+            SendTimeoutError::Disconnected(value) => TrySendError::Disconnected(value),
+        })
+    }
+
+    /// Sends a value, blocking for at most `ms` milliseconds while the
+    /// channel is full. If the timeout expires or every Receiver has been
+    /// dropped, the value is given back in the error.
+    pub fn send_timeout(&self, value: T, ms: u32) -> Result<(), SendTimeoutError<T>> {
+        self.channel.send(value, ms)
+    }
+
+    /// Returns true if every Receiver has been dropped, so nothing sent can
+    /// be received.
+    pub fn is_disconnected(&self) -> bool {
+        self.channel.state.lock().receivers == 0
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Receives a value, blocking while the channel is empty.
+    /// Returns None once the channel is empty and every Sender has been dropped.
+    pub fn recv(&self) -> Option<T> {
+        self.channel.recv(WAIT_FOREVER).ok()
+    }
+
+    /// Receives a value if there is one, without blocking.
+    /// Returns an error if the channel is empty, saying whether more values
+    /// can still be sent.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.channel.recv(0).map_err(|err| match err {
+            RecvTimeoutError::Timeout => TryRecvError::Empty,
+            RecvTimeoutError::Disconnected => TryRecvError::Disconnected,
+        })
+    }
+
+    /// Receives a value, blocking for at most `ms` milliseconds while the
+    /// channel is empty. Returns an error if the timeout expires or the
+    /// channel is empty and every Sender has been dropped.
+    pub fn recv_timeout(&self, ms: u32) -> Result<T, RecvTimeoutError> {
+        self.channel.recv(ms)
+    }
+
+    /// Returns true if every Sender has been dropped and every value sent
+    /// has been received.
+    pub fn is_disconnected(&self) -> bool {
+        let state = self.channel.state.lock();
+        state.senders == 0 && state.queue.is_empty()
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.channel.state.lock().senders += 1;
+
+        Sender {
+            channel: self.channel.clone(),
+        }
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.channel.state.lock().receivers += 1;
+
+        Receiver {
+            channel: self.channel.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let mut state = self.channel.state.lock();
+        state.senders -= 1;
+
+        //Wake up receivers waiting on an empty channel so they can see it's disconnected
+        if state.senders == 0 {
+            self.channel.not_empty.notify_all();
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let mut state = self.channel.state.lock();
+        state.receivers -= 1;
+
+        //Wake up senders waiting on a full channel so they can see it's disconnected
+        if state.receivers == 0 {
+            self.channel.not_full.notify_all();
+        }
+    }
+}
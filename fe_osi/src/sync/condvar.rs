@@ -0,0 +1,116 @@
+use crate::semaphore::Semaphore;
+use crate::sync::MutexGuard;
+use crate::WAIT_FOREVER;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Lets tasks block until another task notifies them that something they are
+/// waiting for may have changed.
+///
+/// A Condvar is used with a Mutex protecting the state being waited on.
+/// Waiting tasks block in the kernel, so they don't use any CPU time.
+/// A task can be woken up without being notified, so the state should be
+/// checked again after waking up.
+///
+/// #Examples
+/// ```
+/// static READY: Mutex<bool> = Mutex::new(false);
+/// static READY_CHANGED: Condvar = Condvar::new();
+///
+/// //Waiting task
+/// let mut ready = READY.lock();
+/// while !*ready {
+///     ready = READY_CHANGED.wait(ready);
+/// }
+///
+/// //Notifying task
+/// *READY.lock() = true;
+/// READY_CHANGED.notify_all();
+/// ```
+#[repr(C)]
+pub struct Condvar {
+    //Counts notifications, so a waiting task can tell whether the Condvar has
+    //been notified since it started waiting
+    seq: AtomicUsize,
+    //Tasks waiting on the Condvar wait in this Semaphore's queue. It's never given.
+    waiters: Semaphore,
+}
+
+extern "C" {
+    //System call to wait until the notification count isn't seq anymore
+    fn do_cond_wait(cond: *const Condvar, seq: usize, timeout: u32) -> usize;
+    //Wakes up one or all of the tasks waiting on a Condvar
+    fn do_cond_wake(cond: *const Condvar, all: bool) -> usize;
+}
+
+impl Condvar {
+    /// Creates a Condvar with no tasks waiting on it.
+    pub const fn new() -> Condvar {
+        Condvar {
+            seq: AtomicUsize::new(0),
+            waiters: Semaphore::new(0),
+        }
+    }
+
+    /// Unlocks the Mutex the guard is for and blocks until the Condvar is
+    /// notified, then locks the Mutex again.
+    pub fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        self.wait_timeout(guard, WAIT_FOREVER).0
+    }
+
+    /// Unlocks the Mutex the guard is for and blocks until the Condvar is
+    /// notified or `ms` milliseconds have passed, then locks the Mutex again.
+    /// The returned bool is true if the timeout expired.
+    pub fn wait_timeout<'a, T: ?Sized>(
+        &self,
+        guard: MutexGuard<'a, T>,
+        ms: u32,
+    ) -> (MutexGuard<'a, T>, bool) {
+        //Read the count before unlocking, so a notification sent right after
+        //unlocking isn't missed. Only notifications sent after this wake us.
+        let seq = self.seq.load(Ordering::SeqCst);
+        let mutex = MutexGuard::unlock(guard);
+
+        let timed_out = unsafe { do_cond_wait(self as *const Condvar, seq, ms) } != 0;
+
+        (mutex.lock(), timed_out)
+    }
+
+    /// Wakes up one of the tasks waiting on the Condvar, if there are any.
+    pub fn notify_one(&self) {
+        self.notify(false);
+    }
+
+    /// Wakes up every task waiting on the Condvar.
+    pub fn notify_all(&self) {
+        self.notify(true);
+    }
+
+    fn notify(&self, all: bool) {
+        self.seq.fetch_add(1, Ordering::SeqCst);
+
+        if self.waiters.raw_waiters().load(Ordering::SeqCst) != 0 {
+            unsafe {
+                do_cond_wake(self as *const Condvar, all);
+            }
+        }
+    }
+
+    /// Returns the number of times the Condvar has been notified, wrapping
+    /// around. This is used by the kernel to tell if a task was notified.
+    #[doc(hidden)]
+    pub fn raw_seq(&self) -> usize {
+        self.seq.load(Ordering::SeqCst)
+    }
+
+    /// Returns the Semaphore tasks wait in the queue of.
+    #[doc(hidden)]
+    pub fn raw_waiters(&self) -> &Semaphore {
+        &self.waiters
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,172 @@
+use crate::semaphore::Semaphore;
+use crate::WAIT_FOREVER;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// A word of 32 flags that tasks can set and clear, and wait on until any or
+/// all of a set of flags are set.
+///
+/// #Examples
+/// ```
+/// const UART_READY: u32 = 1 << 0;
+/// const IMU_READY: u32 = 1 << 1;
+/// static DRIVERS: EventGroup = EventGroup::new();
+///
+/// //Each driver task sets its flag once it's ready
+/// DRIVERS.set(UART_READY);
+///
+/// //Tasks that need every driver wait for all of the flags
+/// DRIVERS.wait_all(UART_READY | IMU_READY, false);
+/// ```
+#[repr(C)]
+pub struct EventGroup {
+    bits: AtomicU32,
+    //Tasks waiting for flags wait in this Semaphore's queue. It's never given.
+    waiters: Semaphore,
+}
+
+/// What a task waiting on an EventGroup passes to the kernel.
+#[repr(C)]
+pub struct EventWait {
+    pub mask: u32,
+    pub wait_for_all: bool,
+    pub clear_on_exit: bool,
+    /// The flags when the wait ended. This is set by the kernel.
+    pub bits: u32,
+}
+
+extern "C" {
+    fn do_event_wait(group: *const EventGroup, wait: *mut EventWait, timeout: u32) -> usize;
+    //Wakes up the tasks waiting for flags that are now set. Like Semaphore::give,
+    //this can be called from an interrupt handler.
+    fn do_event_wake(group: *const EventGroup) -> usize;
+}
+
+impl EventGroup {
+    /// Creates an EventGroup with every flag cleared.
+    pub const fn new() -> EventGroup {
+        EventGroup {
+            bits: AtomicU32::new(0),
+            waiters: Semaphore::new(0),
+        }
+    }
+
+    /// Returns the current flags.
+    pub fn get(&self) -> u32 {
+        self.bits.load(Ordering::SeqCst)
+    }
+
+    /// Sets the flags in `bits` and wakes up any task waiting for them.
+    /// Returns the flags after they were set.
+    /// This can be called from an interrupt handler.
+    pub fn set(&self, bits: u32) -> u32 {
+        let new_bits = self.bits.fetch_or(bits, Ordering::SeqCst) | bits;
+
+        if self.waiters.raw_waiters().load(Ordering::SeqCst) != 0 {
+            unsafe {
+                do_event_wake(self as *const EventGroup);
+            }
+        }
+
+        new_bits
+    }
+
+    /// Clears the flags in `bits`. Returns the flags before they were cleared.
+    pub fn clear(&self, bits: u32) -> u32 {
+        self.bits.fetch_and(!bits, Ordering::SeqCst)
+    }
+
+    /// Blocks until any of the flags in `mask` are set and returns the flags.
+    /// If clear_on_exit is true, the flags in `mask` are cleared before returning.
+    pub fn wait_any(&self, mask: u32, clear_on_exit: bool) -> u32 {
+        self.wait_forever(mask, false, clear_on_exit)
+    }
+
+    /// Blocks until all of the flags in `mask` are set and returns the flags.
+    /// If clear_on_exit is true, the flags in `mask` are cleared before returning.
+    pub fn wait_all(&self, mask: u32, clear_on_exit: bool) -> u32 {
+        self.wait_forever(mask, true, clear_on_exit)
+    }
+
+    fn wait_forever(&self, mask: u32, wait_for_all: bool, clear_on_exit: bool) -> u32 {
+        match self.wait_timeout(mask, wait_for_all, clear_on_exit, WAIT_FOREVER) {
+            Ok(bits) | Err(bits) => bits,
+        }
+    }
+
+    /// Blocks for at most `ms` milliseconds until all of the flags in `mask`
+    /// are set if wait_for_all is true, or any of them otherwise.
+    /// If clear_on_exit is true, the flags in `mask` are cleared when the wait succeeds.
+    ///
+    /// Returns Ok with the flags from before they were cleared, or Err with
+    /// the current flags if the timeout expired.
+    pub fn wait_timeout(
+        &self,
+        mask: u32,
+        wait_for_all: bool,
+        clear_on_exit: bool,
+        ms: u32,
+    ) -> Result<u32, u32> {
+        if let Some(bits) = self.raw_try_wait(mask, wait_for_all, clear_on_exit) {
+            return Ok(bits);
+        }
+
+        let mut wait = EventWait {
+            mask,
+            wait_for_all,
+            clear_on_exit,
+            bits: 0,
+        };
+
+        if unsafe { do_event_wait(self as *const EventGroup, &mut wait, ms) } == 0 {
+            Ok(wait.bits)
+        } else {
+            Err(wait.bits)
+        }
+    }
+
+    /// Returns the flags if the wait condition is met, clearing the flags in
+    /// `mask` if clear_on_exit is true. This is used by the kernel to
+    /// implement `wait_timeout`.
+    #[doc(hidden)]
+    pub fn raw_try_wait(&self, mask: u32, wait_for_all: bool, clear_on_exit: bool) -> Option<u32> {
+        let bits = self
+            .bits
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |bits| {
+                if !is_met(bits, mask, wait_for_all) {
+                    None
+                } else if clear_on_exit {
+                    Some(bits & !mask)
+                } else {
+                    Some(bits)
+                }
+            });
+
+        bits.ok()
+    }
+
+    /// Returns true if the wait condition is met.
+    #[doc(hidden)]
+    pub fn raw_is_met(&self, mask: u32, wait_for_all: bool) -> bool {
+        is_met(self.get(), mask, wait_for_all)
+    }
+
+    /// Returns the Semaphore tasks wait in the queue of.
+    #[doc(hidden)]
+    pub fn raw_waiters(&self) -> &Semaphore {
+        &self.waiters
+    }
+}
+
+impl Default for EventGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn is_met(bits: u32, mask: u32, wait_for_all: bool) -> bool {
+    if wait_for_all {
+        bits & mask == mask
+    } else {
+        bits & mask != 0
+    }
+}
@@ -0,0 +1,28 @@
+use crate::time;
+use crate::WAIT_FOREVER;
+
+mod barrier;
+mod channel;
+mod condvar;
+mod event_group;
+mod mutex;
+mod rwlock;
+
+pub use barrier::{Barrier, BarrierWaitResult};
+pub use channel::{
+    channel, Receiver, RecvTimeoutError, SendTimeoutError, Sender, TryRecvError, TrySendError,
+};
+pub use condvar::Condvar;
+pub use event_group::{EventGroup, EventWait};
+pub use mutex::{Mutex, MutexGuard, RawMutex};
+pub use rwlock::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+//Returns how many of the ms milliseconds after the start tick are left
+pub(crate) fn remaining(start: u64, ms: u32) -> u32 {
+    if ms == WAIT_FOREVER {
+        return WAIT_FOREVER;
+    }
+
+    let elapsed = time::ticks_to_ms(time::ticks() - start);
+    ms.saturating_sub(elapsed.min(u32::MAX as u64) as u32)
+}
@@ -0,0 +1,254 @@
+use crate::semaphore::Semaphore;
+use crate::WAIT_FOREVER;
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// A lock that can only be released by the task that holds it.
+///
+/// While a task is blocked waiting for the RawMutex, the task that holds it
+/// runs with at least the blocked task's priority, so a lower priority task
+/// holding the RawMutex can't keep a higher priority task waiting indefinitely.
+#[repr(C)]
+pub struct RawMutex {
+    sem: Semaphore,
+    //The pid of the task that holds the RawMutex, or 0 if it is unlocked
+    owner: AtomicUsize,
+}
+
+extern "C" {
+    fn do_mutex_lock(mutex: *const RawMutex, timeout: u32) -> usize;
+    fn do_mutex_unlock(mutex: *const RawMutex) -> usize;
+}
+
+impl RawMutex {
+    /// Creates an unlocked RawMutex.
+    pub const fn new() -> RawMutex {
+        RawMutex {
+            sem: Semaphore::new_mutex(),
+            owner: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the pid of the task that holds the RawMutex, or None if it is unlocked.
+    pub fn owner(&self) -> Option<usize> {
+        match self.owner.load(Ordering::SeqCst) {
+            0 => None,
+            pid => Some(pid),
+        }
+    }
+
+    /// Locks the RawMutex. If another task holds the RawMutex, block until it is released.
+    pub fn lock(&self) {
+        //Never carry on without the RawMutex, even if a wait is cut short
+        while !self.lock_timeout(WAIT_FOREVER) {}
+    }
+
+    /// Attempts to lock the RawMutex. Returns true if the RawMutex was locked.
+    pub fn try_lock(&self) -> bool {
+        self.lock_timeout(0)
+    }
+
+    /// Attempts to lock the RawMutex, blocking for at most `ms` milliseconds.
+    /// Returns true if the RawMutex was locked.
+    pub fn lock_timeout(&self, ms: u32) -> bool {
+        unsafe { do_mutex_lock(self as *const RawMutex, ms) == 0 }
+    }
+
+    /// Releases the RawMutex. Only the task that holds the RawMutex can release it.
+    pub fn unlock(&self) -> Result<(), &'static str> {
+        if unsafe { do_mutex_unlock(self as *const RawMutex) } == 0 {
+            Ok(())
+        } else {
+            Err("Mutex is not held by this task.")
+        }
+    }
+
+    /// Locks the RawMutex, executes the closure, then unlocks the RawMutex.
+    pub fn with_lock<F: FnMut()>(&self, mut f: F) {
+        self.lock();
+        f();
+        self.unlock().ok();
+    }
+
+    /// Attempts to lock the RawMutex. If it succeeds, execute the closure,
+    /// return true, then unlock the RawMutex. Otherwise, it will return false.
+    pub fn try_with_lock<F: FnMut()>(&self, mut f: F) -> bool {
+        if self.try_lock() {
+            f();
+            self.unlock().ok();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Locks the RawMutex for the task with the given pid if it is unlocked.
+    /// This is used by the kernel to implement `lock`.
+    #[doc(hidden)]
+    pub fn raw_try_lock(&self, pid: usize) -> bool {
+        if self.sem.try_take() {
+            self.owner.store(pid, Ordering::SeqCst);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Unlocks the RawMutex if it is held by the task with the given pid.
+    /// This is used by the kernel to implement `unlock`.
+    #[doc(hidden)]
+    pub fn raw_unlock(&self, pid: usize) -> bool {
+        if self
+            .owner
+            .compare_exchange(pid, 0, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            self.sem.give();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the Semaphore a task blocks on while waiting for the RawMutex.
+    #[doc(hidden)]
+    pub fn raw_semaphore(&self) -> &Semaphore {
+        &self.sem
+    }
+}
+
+impl Default for RawMutex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A priority inheriting lock that protects the data it holds.
+///
+/// The data can only be accessed through the MutexGuard returned when the
+/// Mutex is locked. The Mutex is unlocked when the MutexGuard is dropped.
+///
+/// #Examples
+/// ```
+/// static COUNT: Mutex<u32> = Mutex::new(0);
+///
+/// *COUNT.lock() += 1;
+///
+/// if let Some(mut count) = COUNT.lock_timeout(10) {
+///     *count += 1;
+/// }
+/// ```
+pub struct Mutex<T: ?Sized> {
+    raw: RawMutex,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for Mutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    /// Creates an unlocked Mutex holding `data`.
+    pub const fn new(data: T) -> Mutex<T> {
+        Mutex {
+            raw: RawMutex::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Consumes the Mutex and returns the data it holds.
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// Locks the Mutex, blocking until it is available.
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        self.raw.lock();
+        MutexGuard::new(self)
+    }
+
+    /// Attempts to lock the Mutex without blocking.
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        if self.raw.try_lock() {
+            Some(MutexGuard::new(self))
+        } else {
+            None
+        }
+    }
+
+    /// Attempts to lock the Mutex, blocking for at most `ms` milliseconds.
+    pub fn lock_timeout(&self, ms: u32) -> Option<MutexGuard<'_, T>> {
+        if self.raw.lock_timeout(ms) {
+            Some(MutexGuard::new(self))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the pid of the task that holds the Mutex, or None if it is unlocked.
+    pub fn owner(&self) -> Option<usize> {
+        self.raw.owner()
+    }
+
+    /// Returns a mutable reference to the data. No locking is needed since
+    /// the Mutex is mutably borrowed.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+/// Gives access to the data in a locked Mutex and unlocks it when dropped.
+///
+/// A MutexGuard can't be sent to another task, since only the task that
+/// locked the Mutex can unlock it.
+pub struct MutexGuard<'a, T: ?Sized> {
+    mutex: &'a Mutex<T>,
+    _not_send: PhantomData<*const ()>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for MutexGuard<'_, T> {}
+
+impl<'a, T: ?Sized> MutexGuard<'a, T> {
+    fn new(mutex: &'a Mutex<T>) -> MutexGuard<'a, T> {
+        MutexGuard {
+            mutex,
+            _not_send: PhantomData,
+        }
+    }
+
+    //Unlocks the Mutex and returns it so it can be locked again later
+    pub(super) fn unlock(guard: Self) -> &'a Mutex<T> {
+        let mutex = guard.mutex;
+        drop(guard);
+        mutex
+    }
+}
+
+impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.raw.unlock().ok();
+    }
+}
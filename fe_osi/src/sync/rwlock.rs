@@ -0,0 +1,252 @@
+use crate::sync::{remaining, Condvar, Mutex, MutexGuard};
+use crate::time;
+use crate::WAIT_FOREVER;
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+/// A lock that lets any number of tasks read the data it holds at once, or
+/// one task write to it.
+///
+/// Writers are preferred. Once a task is waiting to write, tasks that want to
+/// read wait until it has written, so frequent readers can't starve a writer.
+///
+/// #Examples
+/// ```
+/// static CONFIG: RwLock<[u32; 4]> = RwLock::new([0; 4]);
+///
+/// let gain = CONFIG.read()[0];
+///
+/// if let Some(mut config) = CONFIG.write_timeout(10) {
+///     config[0] = 2;
+/// }
+/// ```
+pub struct RwLock<T: ?Sized> {
+    state: Mutex<RwLockState>,
+    //Notified when tasks waiting to read may be able to
+    can_read: Condvar,
+    //Notified when a task waiting to write may be able to
+    can_write: Condvar,
+    data: UnsafeCell<T>,
+}
+
+struct RwLockState {
+    readers: usize,
+    writer: bool,
+    waiting_writers: usize,
+}
+
+impl RwLockState {
+    fn can_read(&self) -> bool {
+        !self.writer && self.waiting_writers == 0
+    }
+
+    fn can_write(&self) -> bool {
+        !self.writer && self.readers == 0
+    }
+}
+
+unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+
+impl<T> RwLock<T> {
+    /// Creates an unlocked RwLock holding `data`.
+    pub const fn new(data: T) -> RwLock<T> {
+        RwLock {
+            state: Mutex::new(RwLockState {
+                readers: 0,
+                writer: false,
+                waiting_writers: 0,
+            }),
+            can_read: Condvar::new(),
+            can_write: Condvar::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Consumes the RwLock and returns the data it holds.
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// Locks the RwLock for reading, blocking until no task is writing or
+    /// waiting to write.
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        match self.read_timeout(WAIT_FOREVER) {
+            Some(guard) => guard,
+            None => unreachable!(),
+        }
+    }
+
+    /// Attempts to lock the RwLock for reading without blocking.
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        self.read_timeout(0)
+    }
+
+    /// Attempts to lock the RwLock for reading, blocking for at most `ms` milliseconds.
+    pub fn read_timeout(&self, ms: u32) -> Option<RwLockReadGuard<'_, T>> {
+        let start = time::ticks();
+        let mut state = self.lock_state(start, ms)?;
+
+        while !state.can_read() {
+            let (new_state, timed_out) = self.can_read.wait_timeout(state, remaining(start, ms));
+            state = new_state;
+
+            if timed_out && !state.can_read() {
+                return None;
+            }
+        }
+
+        state.readers += 1;
+        Some(RwLockReadGuard::new(self))
+    }
+
+    /// Locks the RwLock for writing, blocking until no other task is reading
+    /// or writing.
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        match self.write_timeout(WAIT_FOREVER) {
+            Some(guard) => guard,
+            None => unreachable!(),
+        }
+    }
+
+    /// Attempts to lock the RwLock for writing without blocking.
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        self.write_timeout(0)
+    }
+
+    /// Attempts to lock the RwLock for writing, blocking for at most `ms` milliseconds.
+    pub fn write_timeout(&self, ms: u32) -> Option<RwLockWriteGuard<'_, T>> {
+        let start = time::ticks();
+        let mut state = self.lock_state(start, ms)?;
+
+        state.waiting_writers += 1;
+        while !state.can_write() {
+            let (new_state, timed_out) = self.can_write.wait_timeout(state, remaining(start, ms));
+            state = new_state;
+
+            if timed_out && !state.can_write() {
+                state.waiting_writers -= 1;
+                //Readers may have only been waiting because we were
+                self.wake_waiting(&state);
+                return None;
+            }
+        }
+        state.waiting_writers -= 1;
+
+        state.writer = true;
+        Some(RwLockWriteGuard::new(self))
+    }
+
+    /// Returns a mutable reference to the data. No locking is needed since
+    /// the RwLock is mutably borrowed.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    //Locks the state for what's left of the timeout, so trying to lock the
+    //RwLock doesn't block behind another task holding the state
+    fn lock_state(&self, start: u64, ms: u32) -> Option<MutexGuard<'_, RwLockState>> {
+        self.state.lock_timeout(remaining(start, ms))
+    }
+
+    fn unlock_read(&self) {
+        let mut state = self.state.lock();
+        state.readers -= 1;
+        self.wake_waiting(&state);
+    }
+
+    fn unlock_write(&self) {
+        let mut state = self.state.lock();
+        state.writer = false;
+        self.wake_waiting(&state);
+    }
+
+    //Wakes up a waiting writer if it can write now, or every waiting reader
+    //if they can read now
+    fn wake_waiting(&self, state: &MutexGuard<'_, RwLockState>) {
+        if state.waiting_writers > 0 {
+            if state.can_write() {
+                self.can_write.notify_one();
+            }
+        } else if state.can_read() {
+            self.can_read.notify_all();
+        }
+    }
+}
+
+impl<T: Default> Default for RwLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+/// Gives read access to the data in an RwLock and unlocks it when dropped.
+pub struct RwLockReadGuard<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+    _not_send: PhantomData<*const ()>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for RwLockReadGuard<'_, T> {}
+
+impl<'a, T: ?Sized> RwLockReadGuard<'a, T> {
+    fn new(lock: &'a RwLock<T>) -> RwLockReadGuard<'a, T> {
+        RwLockReadGuard {
+            lock,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.unlock_read();
+    }
+}
+
+/// Gives write access to the data in an RwLock and unlocks it when dropped.
+pub struct RwLockWriteGuard<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+    _not_send: PhantomData<*const ()>,
+}
+
+unsafe impl<T: ?Sized + Sync> Sync for RwLockWriteGuard<'_, T> {}
+
+impl<'a, T: ?Sized> RwLockWriteGuard<'a, T> {
+    fn new(lock: &'a RwLock<T>) -> RwLockWriteGuard<'a, T> {
+        RwLockWriteGuard {
+            lock,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.unlock_write();
+    }
+}
@@ -1,47 +1,475 @@
 extern crate alloc;
 use crate::semaphore::Semaphore;
+use crate::sync::Mutex;
 use alloc::boxed::Box;
+use alloc::string::String;
 use alloc::sync::Arc;
-use core::ptr::null_mut;
+use alloc::vec::Vec;
+use core::ptr::null;
+use cstr_core::{c_char, CString};
+
+/// The default and recommended stack size for a task.
+pub const DEFAULT_STACK_SIZE: usize = 1536;
+/// The smallest stack size in words a task can be spawned with. This only
+/// leaves room for the task's saved context, so real tasks need much more.
+pub const MIN_STACK_SIZE: usize = 64;
+/// The default priority of a task.
+pub const DEFAULT_PRIORITY: usize = 1;
+/// The default number of ticks a task runs before another task of the same
+/// priority gets to run.
+pub const DEFAULT_TIME_SLICE: usize = 1;
+
+/// The attributes of a new task that are passed to the kernel.
+#[repr(C)]
+pub struct TaskAttributes {
+    pub name: *const c_char,
+    pub stack_size: usize,
+    pub priority: usize,
+    pub time_slice: usize,
+    pub suspended: bool,
+    pub period: usize,
+    pub deadline: usize,
+}
+
+/// What the kernel returns when it spawns a task.
+#[repr(C)]
+pub struct SpawnedTask {
+    pub sem: *const Semaphore,
+    pub pid: usize,
+}
 
 extern "C" {
-    fn do_task_spawn(
-        stack_size: usize,
-        entry_point: *const u32,
-        parameter: *mut u32,
-    ) -> *const Semaphore;
+    fn do_task_spawn(attributes: *const TaskAttributes, entry: *mut u32) -> SpawnedTask;
+    fn do_task_resume(pid: usize) -> usize;
+    fn do_task_suspend(pid: usize) -> usize;
+    fn do_task_kill(pid: usize) -> usize;
+    fn do_task_deadline_misses(pid: usize, misses: *mut usize) -> usize;
+    fn do_task_list(list: *mut u32) -> usize;
+    fn do_task_cpu_usage(pid: usize, usage: *mut CpuUsage) -> usize;
+    fn do_task_stack_high_water(pid: usize, high_water: *mut usize) -> usize;
+}
+
+/// The pid to pass to `cpu_usage` to find out how long the CPU has been idle.
+pub const IDLE_PID: usize = 0;
+
+/// How much of the CPU a task has used.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuUsage {
+    /// The total number of ticks the task has spent running.
+    pub ticks: u64,
+    /// The percent of the CPU the task used during the last usage window.
+    pub percent: usize,
+}
+
+/// The state a task was in when a snapshot of it was taken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaskStatus {
+    Runnable,
+    Asleep,
+    Blocking,
+    Suspended,
+    Zombie,
+}
+
+/// A snapshot of a task returned by `task_list`.
+#[derive(Clone, Debug)]
+pub struct TaskInfo {
+    pub pid: usize,
+    pub name: Option<String>,
+    pub state: TaskStatus,
+    /// The size of the task's stack in words.
+    pub stack_size: usize,
+    /// The most words of its stack the task has used so far.
+    pub stack_high_water: usize,
+    pub priority: usize,
+    pub cpu_usage: CpuUsage,
+}
+
+/// The closure a spawned task runs. The task spawn syscall is given a
+/// pointer to a boxed TaskEntry, so the kernel can run or drop it with its
+/// real type.
+pub type TaskEntry = Box<dyn FnOnce() + Send + 'static>;
+
+fn spawn_base<F, R>(attributes: &TaskAttributes, f: F) -> Result<JoinHandle<R>, &'static str>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let result = Arc::new(Mutex::new(None));
+    let task_result = Arc::clone(&result);
+    let entry: TaskEntry = Box::new(move || {
+        let ret_val = f();
+        *task_result.lock() = Some(ret_val);
+    });
+
+    unsafe {
+        let spawned = do_task_spawn(attributes, Box::into_raw(Box::new(entry)) as *mut u32);
+        if spawned.sem.is_null() {
+            return Err("Stack size is too small.");
+        }
+
+        Ok(JoinHandle {
+            pid: spawned.pid,
+            sem: Arc::from_raw(spawned.sem),
+            result,
+        })
+    }
 }
 
-/// Creates a new task that will be run.
+/// Creates a new task that will run the closure.
+/// A stack_size smaller than `MIN_STACK_SIZE` is raised to it.
 ///
 /// #Examples
 /// ```
-/// fn test_task(_: &mut u8) {
+/// let val: u32 = 21;
+/// let handle = task_spawn(1024, move || val * 2);
+/// assert_eq!(handle.join(), Ok(42));
+/// ```
+pub fn task_spawn<F, R>(stack_size: usize, f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let attributes = TaskAttributes {
+        name: null(),
+        stack_size: stack_size.max(MIN_STACK_SIZE),
+        priority: DEFAULT_PRIORITY,
+        time_slice: DEFAULT_TIME_SLICE,
+        suspended: false,
+        period: 0,
+        deadline: 0,
+    };
+
+    match spawn_base(&attributes, f) {
+        Ok(handle) => handle,
+        Err(msg) => panic!("{}", msg),
+    }
+}
+
+/// Lets a suspended task run again.
+pub fn task_resume(pid: usize) -> Result<(), &'static str> {
+    if unsafe { do_task_resume(pid) } == 0 {
+        Ok(())
+    } else {
+        Err("No suspended task with that pid.")
+    }
+}
+
+/// Stops the task with the given pid from running until it is resumed.
+/// A task can suspend itself.
+pub fn task_suspend(pid: usize) -> Result<(), &'static str> {
+    if unsafe { do_task_suspend(pid) } == 0 {
+        Ok(())
+    } else {
+        Err("No task with that pid can be suspended.")
+    }
+}
+
+/// Terminates the task with the given pid. The task is cleaned up the same
+/// way it would be if it exited, so anything blocked on its exit is woken up.
+///
+/// A task that is in the middle of a system call finishes it first, so it
+/// never leaves the kernel's own locks held. Killing a task that holds one of
+/// its own locks leaves that lock held, so this is best saved for tasks that
+/// have stopped responding.
+pub fn task_kill(pid: usize) -> Result<(), &'static str> {
+    if unsafe { do_task_kill(pid) } == 0 {
+        Ok(())
+    } else {
+        Err("No task with that pid can be killed.")
+    }
+}
+
+/// Returns the number of deadlines the task with the given pid has missed.
+/// Deadlines are only tracked for periodic tasks by a deadline aware scheduler.
+pub fn deadline_misses(pid: usize) -> Option<usize> {
+    let mut misses: usize = 0;
+    if unsafe { do_task_deadline_misses(pid, &mut misses) } == 0 {
+        Some(misses)
+    } else {
+        None
+    }
+}
+
+/// Returns the most words of its stack the task with the given pid has used
+/// so far. This is useful for picking stack sizes from measurements.
+pub fn stack_high_water(pid: usize) -> Option<usize> {
+    let mut high_water: usize = 0;
+    if unsafe { do_task_stack_high_water(pid, &mut high_water) } == 0 {
+        Some(high_water)
+    } else {
+        None
+    }
+}
+
+/// Returns how much of the CPU the task with the given pid has used.
+/// Passing `IDLE_PID` returns how long the CPU has spent with no task to run.
+pub fn cpu_usage(pid: usize) -> Option<CpuUsage> {
+    let mut usage = CpuUsage::default();
+    if unsafe { do_task_cpu_usage(pid, &mut usage) } == 0 {
+        Some(usage)
+    } else {
+        None
+    }
+}
+
+/// Returns a snapshot of every task that hasn't been cleaned up yet.
+///
+/// #Examples
+/// ```
+/// for task in task_list() {
+///     let name = task.name.as_deref().unwrap_or("-");
+///     let msg = format!("{} {} {:?}\r\n", task.pid, name, task.state);
+/// }
+/// ```
+pub fn task_list() -> Vec<TaskInfo> {
+    let mut list: Vec<TaskInfo> = Vec::new();
+    unsafe {
+        do_task_list(&mut list as *mut Vec<TaskInfo> as *mut u32);
+    }
+    list
+}
+
+/// Configures the attributes of a new task and spawns it.
+///
+/// #Examples
+/// ```
+/// fn motor_task() {
 ///     loop {}
 /// }
-/// task_spawn(1024, test_task, None);
+/// let handle = TaskBuilder::new()
+///     .name("motor")
+///     .stack_size(1024)
+///     .priority(4)
+///     .spawn(motor_task)
+///     .unwrap();
 /// ```
-pub fn task_spawn<T: Send>(
+pub struct TaskBuilder {
+    name: Option<String>,
     stack_size: usize,
-    entry_point: fn(&mut T),
-    parameter: Option<Box<T>>,
-) -> Arc<Semaphore> {
-    unsafe {
-        let param_ptr = match parameter {
-            Some(param) => Box::into_raw(param) as *mut u32,
-            None => null_mut(),
+    priority: usize,
+    time_slice: usize,
+    suspended: bool,
+    period: usize,
+    deadline: usize,
+}
+
+impl TaskBuilder {
+    /// Creates a TaskBuilder with the default task attributes.
+    pub fn new() -> Self {
+        TaskBuilder {
+            name: None,
+            stack_size: DEFAULT_STACK_SIZE,
+            priority: DEFAULT_PRIORITY,
+            time_slice: DEFAULT_TIME_SLICE,
+            suspended: false,
+            period: 0,
+            deadline: 0,
+        }
+    }
+
+    /// Sets the name of the task.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(String::from(name));
+        self
+    }
+
+    /// Sets the stack size of the task in words. It must be at least
+    /// `MIN_STACK_SIZE`.
+    pub fn stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = stack_size;
+        self
+    }
+
+    /// Sets the priority of the task. Higher numbers have higher priority.
+    pub fn priority(mut self, priority: usize) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Sets the number of ticks the task runs before another task of the
+    /// same priority gets to run.
+    pub fn time_slice(mut self, ticks: usize) -> Self {
+        self.time_slice = ticks;
+        self
+    }
+
+    /// If suspended is true, the task will not run until it is resumed.
+    pub fn suspended(mut self, suspended: bool) -> Self {
+        self.suspended = suspended;
+        self
+    }
+
+    /// Makes the task periodic, releasing a new job every `ticks` ticks.
+    /// The task should sleep when it has finished each job.
+    pub fn period(mut self, ticks: usize) -> Self {
+        self.period = ticks;
+        self
+    }
+
+    /// Sets how many ticks after the start of each period the task's job must
+    /// be finished by. This defaults to the period.
+    pub fn deadline(mut self, ticks: usize) -> Self {
+        self.deadline = ticks;
+        self
+    }
+
+    /// Spawns a task with the configured attributes that will run the closure.
+    /// Returns an error if the name has a nul byte in it or the stack size is
+    /// smaller than `MIN_STACK_SIZE`.
+    pub fn spawn<F, R>(self, f: F) -> Result<JoinHandle<R>, &'static str>
+    where
+        F: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        if self.stack_size < MIN_STACK_SIZE {
+            return Err("Stack size is too small.");
+        }
+
+        let c_name = match self.name {
+            Some(name) => match CString::new(name) {
+                Ok(n) => Some(n),
+                Err(_) => return Err("Invalid task name"),
+            },
+            None => None,
+        };
+        let attributes = TaskAttributes {
+            name: c_name.as_ref().map_or(null(), |n| n.as_ptr()),
+            stack_size: self.stack_size,
+            priority: self.priority,
+            time_slice: self.time_slice,
+            suspended: self.suspended,
+            period: self.period,
+            deadline: self.deadline,
         };
-        let raw_sem = do_task_spawn(stack_size, entry_point as *const u32, param_ptr);
-        Arc::from_raw(raw_sem)
+
+        spawn_base(&attributes, f)
     }
 }
 
-/// Creates a new task that will be run and blocks until that task exits.
-pub fn task_spawn_block<T: Send>(
-    stack_size: usize,
-    entry_point: fn(&mut T),
-    parameter: Option<Box<T>>,
-) {
-    let sem = task_spawn(stack_size, entry_point, parameter);
-    sem.take();
+impl Default for TaskBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs a loop once every period without drifting, by sleeping until the
+/// start of the next period instead of for a length of time.
+///
+/// #Examples
+/// ```
+/// let mut periodic = Periodic::new(10);
+/// loop {
+///     read_sensors();
+///     if !periodic.wait() {
+///         print_msg("Sensor loop overran\r\n");
+///     }
+/// }
+/// ```
+pub struct Periodic {
+    period: u64,
+    //The tick the current period started on
+    last_wake: u64,
+    overruns: usize,
+}
+
+impl Periodic {
+    /// Creates a Periodic with a period of `ticks` ticks, starting now.
+    pub fn new(ticks: u64) -> Self {
+        Periodic {
+            period: ticks.max(1),
+            last_wake: crate::time::ticks(),
+            overruns: 0,
+        }
+    }
+
+    /// Sleeps until the start of the next period and returns true.
+    ///
+    /// If the work done since the last call took longer than a period, this
+    /// returns false right away, counting an overrun. Any periods that were
+    /// missed entirely are skipped so the loop gets back on schedule.
+    pub fn wait(&mut self) -> bool {
+        self.last_wake += self.period;
+
+        if crate::sleep_until(self.last_wake) {
+            return true;
+        }
+
+        let late = crate::time::ticks().saturating_sub(self.last_wake);
+        self.last_wake += late / self.period * self.period;
+        self.overruns += 1;
+
+        false
+    }
+
+    /// Returns the number of times the loop has overrun its period.
+    pub fn overruns(&self) -> usize {
+        self.overruns
+    }
+}
+
+/// A handle to a spawned task that can be used to wait for the task to
+/// finish and get the value it returned.
+pub struct JoinHandle<R> {
+    pid: usize,
+    sem: Arc<Semaphore>,
+    result: Arc<Mutex<Option<R>>>,
+}
+
+impl<R> JoinHandle<R> {
+    /// Returns the pid of the task.
+    pub fn pid(&self) -> usize {
+        self.pid
+    }
+
+    /// Lets the task run again if it was suspended.
+    pub fn resume(&self) -> Result<(), &'static str> {
+        task_resume(self.pid)
+    }
+
+    /// Stops the task from running until it is resumed.
+    pub fn suspend(&self) -> Result<(), &'static str> {
+        task_suspend(self.pid)
+    }
+
+    /// Terminates the task. Joining the task afterwards returns an error.
+    pub fn kill(&self) -> Result<(), &'static str> {
+        task_kill(self.pid)
+    }
+
+    /// Returns the number of deadlines the task has missed, or None if the
+    /// task has exited.
+    pub fn deadline_misses(&self) -> Option<usize> {
+        deadline_misses(self.pid)
+    }
+
+    /// Returns the most words of its stack the task has used so far, or None
+    /// if the task has exited.
+    pub fn stack_high_water(&self) -> Option<usize> {
+        stack_high_water(self.pid)
+    }
+
+    /// Returns how much of the CPU the task has used, or None if the task
+    /// has exited.
+    pub fn cpu_usage(&self) -> Option<CpuUsage> {
+        cpu_usage(self.pid)
+    }
+
+    /// Returns true if the task has exited.
+    pub fn is_finished(&self) -> bool {
+        self.sem.is_available()
+    }
+
+    /// Blocks until the task exits and returns the value it returned.
+    /// If the task exited without returning, such as by calling `exit`,
+    /// an error is returned instead.
+    pub fn join(self) -> Result<R, &'static str> {
+        self.sem.take();
+
+        match self.result.lock().take() {
+            Some(result) => Ok(result),
+            None => Err("Task exited without returning a value."),
+        }
+    }
 }
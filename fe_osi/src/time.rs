@@ -0,0 +1,154 @@
+use core::ops::{Add, AddAssign, Sub, SubAssign};
+pub use core::time::Duration;
+
+extern "C" {
+    fn do_get_ticks() -> u64;
+    fn do_get_tick_hz() -> usize;
+}
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+/// Returns the number of ticks since the scheduler started.
+pub fn ticks() -> u64 {
+    unsafe { do_get_ticks() }
+}
+
+/// Returns the number of ticks in a second.
+pub fn tick_hz() -> u64 {
+    unsafe { do_get_tick_hz() as u64 }
+}
+
+/// Returns how long it has been since the scheduler started.
+pub fn uptime() -> Duration {
+    ticks_to_duration(ticks())
+}
+
+/// Converts a number of ticks into a Duration.
+pub fn ticks_to_duration(ticks: u64) -> Duration {
+    let hz = tick_hz();
+    let nanos = (ticks % hz) as u128 * NANOS_PER_SEC / hz as u128;
+
+    Duration::new(ticks / hz, nanos as u32)
+}
+
+/// Converts a Duration into a number of ticks, rounding up so that waiting
+/// that many ticks takes at least the Duration.
+pub fn duration_to_ticks(duration: Duration) -> u64 {
+    let ticks = (duration.as_nanos() * tick_hz() as u128).div_ceil(NANOS_PER_SEC);
+
+    ticks.min(u64::MAX as u128) as u64
+}
+
+/// Converts a number of ticks into milliseconds, rounding down.
+pub fn ticks_to_ms(ticks: u64) -> u64 {
+    (ticks as u128 * 1_000 / tick_hz() as u128) as u64
+}
+
+/// Converts a number of ticks into microseconds, rounding down.
+pub fn ticks_to_us(ticks: u64) -> u64 {
+    (ticks as u128 * 1_000_000 / tick_hz() as u128) as u64
+}
+
+/// Converts milliseconds into a number of ticks, rounding up.
+pub fn ms_to_ticks(ms: u64) -> u64 {
+    duration_to_ticks(Duration::from_millis(ms))
+}
+
+/// Converts microseconds into a number of ticks, rounding up.
+pub fn us_to_ticks(us: u64) -> u64 {
+    duration_to_ticks(Duration::from_micros(us))
+}
+
+/// A point in time, measured in ticks since the scheduler started.
+///
+/// #Examples
+/// ```
+/// let start = Instant::now();
+/// do_work();
+/// let msg = format!("Work took {} us\r\n", start.elapsed().as_micros());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant {
+    ticks: u64,
+}
+
+impl Instant {
+    /// Returns the current time.
+    pub fn now() -> Instant {
+        Instant { ticks: ticks() }
+    }
+
+    /// Returns the Instant the given number of ticks after the scheduler started.
+    pub fn from_ticks(ticks: u64) -> Instant {
+        Instant { ticks }
+    }
+
+    /// Returns the number of ticks between the scheduler starting and the Instant.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Returns how long it has been since the Instant.
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().duration_since(*self)
+    }
+
+    /// Returns how long after `earlier` the Instant is, or zero if `earlier`
+    /// is later than the Instant.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        ticks_to_duration(self.ticks.saturating_sub(earlier.ticks))
+    }
+
+    /// Returns the Instant `duration` after this one, or None if it would overflow.
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        self.ticks
+            .checked_add(duration_to_ticks(duration))
+            .map(Instant::from_ticks)
+    }
+
+    /// Returns the Instant `duration` before this one, or None if it would be
+    /// before the scheduler started.
+    pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+        self.ticks
+            .checked_sub(duration_to_ticks(duration))
+            .map(Instant::from_ticks)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, duration: Duration) -> Instant {
+        self.checked_add(duration)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, duration: Duration) {
+        *self = *self + duration;
+    }
+}
+
+impl Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, duration: Duration) -> Instant {
+        self.checked_sub(duration)
+            .expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, duration: Duration) {
+        *self = *self - duration;
+    }
+}
+
+impl Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, earlier: Instant) -> Duration {
+        self.duration_since(earlier)
+    }
+}
@@ -0,0 +1,115 @@
+extern crate alloc;
+use alloc::boxed::Box;
+
+/// The callback a timer runs. The timer create syscall is given a pointer to
+/// a boxed TimerCallback, so the kernel can run or drop it with its real type.
+pub type TimerCallback = Box<dyn FnMut() + Send + 'static>;
+
+extern "C" {
+    fn do_timer_create(period: u32, periodic: bool, callback: *mut u32, id: *mut usize) -> usize;
+    fn do_timer_start(id: usize) -> usize;
+    fn do_timer_stop(id: usize) -> usize;
+    fn do_timer_change_period(id: usize, period: u32) -> usize;
+    fn do_timer_delete(id: usize) -> usize;
+}
+
+/// A software timer that calls a closure once its period has passed.
+///
+/// The callbacks of every timer run one at a time in the kernel's timer task,
+/// so a periodic activity doesn't need a task and stack of its own. Callbacks
+/// should be short and shouldn't block, since that delays every other timer.
+///
+/// A timer is stopped when it is created and is deleted when it is dropped.
+///
+/// #Examples
+/// ```
+/// let heartbeat = Timer::periodic(500, || print_msg("beat\r\n")).unwrap();
+/// heartbeat.start().unwrap();
+/// ```
+pub struct Timer {
+    id: usize,
+}
+
+impl Timer {
+    /// Creates a stopped timer that calls `callback` once, `ms` milliseconds
+    /// after it is started.
+    pub fn one_shot<F>(ms: u32, callback: F) -> Result<Timer, &'static str>
+    where
+        F: FnMut() + Send + 'static,
+    {
+        Timer::new(ms, false, callback)
+    }
+
+    /// Creates a stopped timer that calls `callback` every `ms` milliseconds
+    /// once it is started.
+    pub fn periodic<F>(ms: u32, callback: F) -> Result<Timer, &'static str>
+    where
+        F: FnMut() + Send + 'static,
+    {
+        Timer::new(ms, true, callback)
+    }
+
+    fn new<F>(ms: u32, periodic: bool, callback: F) -> Result<Timer, &'static str>
+    where
+        F: FnMut() + Send + 'static,
+    {
+        let callback: TimerCallback = Box::new(callback);
+        let mut id: usize = 0;
+
+        let ret_val = unsafe {
+            do_timer_create(
+                ms,
+                periodic,
+                Box::into_raw(Box::new(callback)) as *mut u32,
+                &mut id,
+            )
+        };
+
+        if ret_val == 0 {
+            Ok(Timer { id })
+        } else {
+            Err("Timer period must be greater than 0.")
+        }
+    }
+
+    /// Starts the timer. If the timer is already running, its period starts over.
+    pub fn start(&self) -> Result<(), &'static str> {
+        if unsafe { do_timer_start(self.id) } == 0 {
+            Ok(())
+        } else {
+            Err("Timer no longer exists.")
+        }
+    }
+
+    /// Stops the timer. Its callback won't be called until it is started again.
+    pub fn stop(&self) -> Result<(), &'static str> {
+        if unsafe { do_timer_stop(self.id) } == 0 {
+            Ok(())
+        } else {
+            Err("Timer no longer exists.")
+        }
+    }
+
+    /// Starts the timer's period over, starting the timer if it was stopped.
+    pub fn reset(&self) -> Result<(), &'static str> {
+        self.start()
+    }
+
+    /// Sets the period of the timer to `ms` milliseconds and starts it over,
+    /// starting the timer if it was stopped.
+    pub fn change_period(&self, ms: u32) -> Result<(), &'static str> {
+        if unsafe { do_timer_change_period(self.id, ms) } == 0 {
+            Ok(())
+        } else {
+            Err("Timer period must be greater than 0.")
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        unsafe {
+            do_timer_delete(self.id);
+        }
+    }
+}
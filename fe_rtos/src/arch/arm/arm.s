@@ -6,6 +6,8 @@
     .global context_switch
     .global disable_interrupts
     .global enable_interrupts
+    .global enter_critical
+    .global exit_critical
     .thumb_func
 context_switch:
     //R0 - Current Task
@@ -52,3 +54,16 @@ disable_interrupts:
 enable_interrupts:
     cpsie if
     BX LR
+
+    //Disables interrupts and returns whether they were enabled before
+    .thumb_func
+enter_critical:
+    MRS R0, PRIMASK
+    cpsid i
+    BX LR
+
+    //Restores the interrupt state returned by enter_critical
+    .thumb_func
+exit_critical:
+    MSR PRIMASK, R0
+    BX LR
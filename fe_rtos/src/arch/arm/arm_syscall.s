@@ -13,8 +13,30 @@
     .weak sys_ipc_get_message
     .weak sys_get_heap_remaining
     .weak sys_interrupt_register
+    .weak sys_task_resume
+    .weak sys_task_deadline_misses
+    .weak sys_mutex_lock
+    .weak sys_mutex_unlock
+    .weak sys_sem_take
+    .weak sys_task_list
+    .weak sys_task_stack_high_water
+    .weak sys_task_suspend
+    .weak sys_task_kill
+    .weak sys_task_cpu_usage
+    .weak sys_timer_create
+    .weak sys_timer_start
+    .weak sys_timer_stop
+    .weak sys_timer_change_period
+    .weak sys_timer_delete
+    .weak sys_sleep_until
+    .weak sys_get_ticks
+    .weak sys_get_tick_hz
+    .weak sys_event_wait
+    .weak sys_cond_wait
     .global svc_handler
-.equ max_svc, 12
+    .weak enter_syscall
+    .weak exit_syscall
+.equ max_svc, 32
 
 ///////////////////////////////////////////////////////////////////////////////
 // Arm Cortex-M interrupt stack frame order:
@@ -64,13 +86,38 @@ svc_handler:
     ORR R1, R1, 1
     STR R1, [SP, 0x14]
 
+    //The syscall is called by syscall_entry, which gets its address in R12
+    STR R0, [SP, 0x10]
+
     //The new PC in the ISR stackframe is where we're jumping to
+    LDR R0, =syscall_entry
     STR R0, [SP, 0x18]
 
     .thumb_func
 svc_handler_end:
     BX LR
 
+///////////////////////////////////////////////////////////////////////////////
+// Runs a syscall in thread mode. The kernel keeps track of when a task is in
+// a syscall so a task isn't killed while it holds any of the kernel's locks.
+// R0-R3 hold the syscall params, R12 holds the address of the syscall and LR
+// is where the syscall returns to.
+///////////////////////////////////////////////////////////////////////////////
+    .thumb_func
+syscall_entry:
+    PUSH { R0-R4, LR }
+    MOV R4, R12
+    BL enter_syscall
+    POP { R0-R3 }
+
+    BLX R4
+
+    //Keep the return value, which can be 64 bits
+    PUSH { R0, R1 }
+    BL exit_syscall
+    POP { R0, R1 }
+    POP { R4, PC }
+
 .align 4
 svc_addr_table:
     .word sys_exit               // 0
@@ -86,3 +133,23 @@ svc_addr_table:
     .word sys_ipc_get_message    // 10
     .word sys_get_heap_remaining // 11
     .word sys_interrupt_register // 12
+    .word sys_task_resume        // 13
+    .word sys_task_deadline_misses // 14
+    .word sys_mutex_lock         // 15
+    .word sys_mutex_unlock       // 16
+    .word sys_sem_take           // 17
+    .word sys_task_list          // 18
+    .word sys_task_stack_high_water// 19
+    .word sys_task_suspend       // 20
+    .word sys_task_kill          // 21
+    .word sys_task_cpu_usage     // 22
+    .word sys_timer_create       // 23
+    .word sys_timer_start        // 24
+    .word sys_timer_stop         // 25
+    .word sys_timer_change_period// 26
+    .word sys_timer_delete       // 27
+    .word sys_sleep_until        // 28
+    .word sys_get_ticks          // 29
+    .word sys_get_tick_hz        // 30
+    .word sys_event_wait         // 31
+    .word sys_cond_wait          // 32
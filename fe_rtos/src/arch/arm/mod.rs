@@ -2,12 +2,26 @@ use crate::task;
 use core::mem::size_of;
 use core::ptr;
 use cortex_m::peripheral::scb::Exception;
+use cortex_m::peripheral::SCB;
+use cortex_m::peripheral::SYST;
+
+const SYST_CSR_ENABLE: u32 = 1 << 0;
+const SYST_CSR_TICKINT: u32 = 1 << 1;
+const SYST_CSR_CLKSOURCE: u32 = 1 << 2;
+#[cfg(feature = "tickless")]
+const SYST_CSR_COUNTFLAG: u32 = 1 << 16;
+const SYST_MAX_RELOAD: u32 = 0x00FF_FFFF;
+//SysTick counts down from its reload value to 0, so its reload value is one
+//less than the number of cycles in a tick
+pub(crate) const MAX_TICK_RELOAD: usize = SYST_MAX_RELOAD as usize + 1;
 
 extern "C" {
     fn context_switch();
     fn svc_handler();
     pub(crate) fn disable_interrupts();
     pub(crate) fn enable_interrupts();
+    pub(crate) fn enter_critical() -> usize;
+    pub(crate) fn exit_critical(state: usize);
 }
 
 /// Sets up everything the arm needs before starting FeRTOS.
@@ -36,10 +50,85 @@ pub fn arch_setup(p: &mut cortex_m::peripheral::Peripherals) {
     }
 }
 
+/// Starts the SysTick interrupt, counting reload_val cycles of the core clock
+/// in each tick.
+pub fn enable_systick(reload_val: usize) {
+    unsafe {
+        let syst = &*SYST::PTR;
+        syst.rvr.write(reload_val.saturating_sub(1) as u32);
+        syst.cvr.write(0);
+        syst.csr
+            .write(SYST_CSR_ENABLE | SYST_CSR_TICKINT | SYST_CSR_CLKSOURCE);
+    }
+}
+
 pub(crate) unsafe fn trigger_context_switch() {
     cortex_m::peripheral::SCB::set_pendsv();
 }
 
+//Stretches the current systick period to last up to ticks ticks and sleeps
+//until an interrupt happens. Interrupts must be disabled when this is called.
+//Returns the number of ticks that passed, not counting the tick sys_tick
+//will handle if the whole sleep passed.
+#[cfg(feature = "tickless")]
+pub(crate) unsafe fn tickless_sleep(ticks: u64) -> u64 {
+    let syst = &*SYST::PTR;
+    let tick_counts = syst.rvr.read() + 1;
+    let ticks = ticks.min((SYST_MAX_RELOAD / tick_counts) as u64) as u32;
+
+    //There's no point in sleeping if a tick is about to be handled anyway
+    if ticks < 2 || SCB::is_pendst_pending() {
+        return 0;
+    }
+
+    //Stop the counter while it's reprogrammed
+    syst.csr.write(syst.csr.read() & !SYST_CSR_ENABLE);
+    //Sleep for what's left of the current tick and ticks - 1 more ticks
+    let tick_left = syst.cvr.read();
+    let sleep_counts = tick_left + tick_counts * (ticks - 1);
+    syst.rvr.write(sleep_counts);
+    syst.cvr.write(0);
+    syst.csr.write(syst.csr.read() | SYST_CSR_ENABLE);
+
+    cortex_m::asm::wfi();
+
+    //Reading the CSR clears the COUNTFLAG, so only read it once
+    let csr = syst.csr.read();
+    syst.csr.write(csr & !SYST_CSR_ENABLE);
+
+    let slept = if csr & SYST_CSR_COUNTFLAG != 0 {
+        //The whole sleep passed and the systick interrupt is pending
+        syst.rvr.write(tick_counts - 1);
+        ticks - 1
+    } else {
+        //Another interrupt woke us up, so finish the current tick before
+        //going back to the normal reload value
+        let elapsed = sleep_counts - syst.cvr.read();
+        let (slept, left) = if elapsed < tick_left {
+            (0, tick_left - elapsed)
+        } else {
+            let since_tick = elapsed - tick_left;
+            (
+                since_tick / tick_counts + 1,
+                tick_counts - since_tick % tick_counts,
+            )
+        };
+        syst.rvr.write(left.max(2) - 1);
+        slept
+    };
+
+    syst.cvr.write(0);
+    syst.csr.write(csr | SYST_CSR_ENABLE);
+    //This takes effect the next time the counter reloads
+    syst.rvr.write(tick_counts - 1);
+
+    slept as u64
+}
+
+pub(crate) unsafe fn reset() -> ! {
+    SCB::sys_reset();
+}
+
 pub(crate) unsafe fn set_canary(stack_bottom: *const usize, _stack_size: usize) -> *const usize {
     //In arm, the canary will be the bottom of the stack
     let canary = stack_bottom as *mut usize;
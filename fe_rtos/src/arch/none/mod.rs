@@ -1,9 +1,28 @@
+pub(crate) const MAX_TICK_RELOAD: usize = usize::MAX;
+
 pub(crate) unsafe fn disable_interrupts() {}
 
 pub(crate) unsafe fn enable_interrupts() {}
 
+pub(crate) unsafe fn enter_critical() -> usize {
+    0
+}
+
+pub(crate) unsafe fn exit_critical(_state: usize) {}
+
+pub fn enable_systick(_reload_val: usize) {}
+
 pub(crate) unsafe fn trigger_context_switch() {}
 
+#[cfg(feature = "tickless")]
+pub(crate) unsafe fn tickless_sleep(_ticks: u64) -> u64 {
+    0
+}
+
+pub(crate) unsafe fn reset() -> ! {
+    loop {}
+}
+
 pub(crate) unsafe fn set_canary(_stack_bottom: *const usize, _stack_size: usize) -> *const usize {
     core::ptr::null()
 }
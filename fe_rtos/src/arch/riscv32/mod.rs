@@ -10,15 +10,22 @@ static mut MTIMECMP: *mut u64 = null_mut();
 static mut INT_HANDLER: [extern "C" fn(); 16] = [interrupt::DefaultExceptionHandler; 16];
 static mut EXC_HANDLER: [extern "C" fn(); 16] = [interrupt::DefaultExceptionHandler; 16];
 static mut RELOAD_VAL: u64 = 0;
+//mtimecmp is 64 bits, so any number of cycles fits in a tick
+pub(crate) const MAX_TICK_RELOAD: usize = usize::MAX;
 
 extern "C" {
     pub(crate) fn disable_interrupts();
     pub(crate) fn enable_interrupts();
+    pub(crate) fn enter_critical() -> usize;
+    pub(crate) fn exit_critical(state: usize);
     pub(crate) fn trigger_context_switch();
     fn set_mtie();
     fn get_mepc() -> usize;
     fn set_mepc(mepc: usize);
     fn setup_interrupts();
+    fn _start() -> !;
+    #[cfg(feature = "tickless")]
+    fn wait_for_interrupt();
 }
 
 /// Sets up everything the arm needs before starting FeRTOS.
@@ -34,6 +41,13 @@ pub unsafe fn arch_setup(mtime: *const u64, mtimecmp: *mut u64) {
     setup_interrupts();
 }
 
+//There's no standard way to reset a RISC-V core, so start over from the
+//beginning with interrupts disabled
+pub(crate) unsafe fn reset() -> ! {
+    disable_interrupts();
+    _start();
+}
+
 pub(crate) unsafe fn set_canary(stack_bottom: *const usize, _stack_size: usize) -> *const usize {
     //In riscv, the canary will be the bottom of the stack
     let canary = stack_bottom as *mut usize;
@@ -84,6 +98,7 @@ pub(crate) unsafe fn int_register(irqn_isize: isize, int_handler: *const usize)
     }
 }
 
+/// Starts the timer interrupt, counting reload_val cycles of mtime in each tick.
 pub fn enable_systick(reload_val: usize) {
     unsafe {
         RELOAD_VAL = reload_val as u64;
@@ -92,6 +107,38 @@ pub fn enable_systick(reload_val: usize) {
     }
 }
 
+//Pushes the next timer interrupt up to ticks ticks away and sleeps until an
+//interrupt happens. Interrupts must be disabled when this is called.
+//Returns the number of ticks that passed, not counting the tick sys_tick
+//will handle if the whole sleep passed.
+#[cfg(feature = "tickless")]
+pub(crate) unsafe fn tickless_sleep(ticks: u64) -> u64 {
+    if ticks < 2 || RELOAD_VAL == 0 {
+        return 0;
+    }
+
+    //MTIMECMP holds when the current tick ends
+    let tick_end = *MTIMECMP;
+    *MTIMECMP = tick_end + RELOAD_VAL * (ticks - 1);
+
+    wait_for_interrupt();
+
+    let now = *MTIME;
+    if now >= *MTIMECMP {
+        //The whole sleep passed and the timer interrupt is pending
+        ticks - 1
+    } else if now < tick_end {
+        *MTIMECMP = tick_end;
+        0
+    } else {
+        //Another interrupt woke us up, so the next timer interrupt should be
+        //at the end of the current tick
+        let slept = (now - tick_end) / RELOAD_VAL + 1;
+        *MTIMECMP = tick_end + RELOAD_VAL * slept;
+        slept
+    }
+}
+
 #[no_mangle]
 unsafe fn interrupt_switch(mcause: usize) {
     let cause = mcause & !INT_MASK;
@@ -11,12 +11,15 @@
 .global context_switch
 .global disable_interrupts
 .global enable_interrupts
+.global enter_critical
+.global exit_critical
 .global set_mtie
 .global get_mepc
 .global set_mepc
 .global setup_interrupts
 .global _start
 .global trigger_context_switch
+.global wait_for_interrupt
 
 .equ regsize, 0x4
 .equ mie_init, 0xB0B
@@ -172,6 +175,21 @@ enable_interrupts:
     csrsi mstatus, mstatus_mie
     ret
 
+#Disables interrupts and returns whether they were enabled before
+enter_critical:
+    csrrci a0, mstatus, mstatus_mie
+    andi a0, a0, mstatus_mie
+    ret
+
+#Restores the interrupt state returned by enter_critical
+exit_critical:
+    csrs mstatus, a0
+    ret
+
+wait_for_interrupt:
+    wfi
+    ret
+
 trigger_context_switch:
     addi sp, sp, -16
     sw ra, 12(sp)
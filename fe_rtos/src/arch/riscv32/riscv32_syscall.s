@@ -15,10 +15,31 @@
 .local sys_ipc_get_message
 .local sys_get_heap_remaining
 .local sys_interrupt_register
+.local sys_task_resume
+.local sys_task_deadline_misses
+.local sys_mutex_lock
+.local sys_mutex_unlock
+.local sys_sem_take
+.local sys_task_list
+.local sys_task_stack_high_water
+.local sys_task_suspend
+.local sys_task_kill
+.local sys_task_cpu_usage
+.local sys_timer_create
+.local sys_timer_start
+.local sys_timer_stop
+.local sys_timer_change_period
+.local sys_timer_delete
+.local sys_sleep_until
+.local sys_get_ticks
+.local sys_get_tick_hz
+.local sys_event_wait
+.local syscall_entry
+.local sys_cond_wait
 .global syscall_handler
 
 .equ context_switch_number, 0xFF
-.equ max_syscall, 12
+.equ max_syscall, 32
 
 syscall_handler:
     #The syscall number is in a7
@@ -48,11 +69,50 @@ syscall_handler:
     add t0, t0, t1
     lw t0, (t0)
 
+    #The syscall is called by syscall_entry, which gets its address in t0
+    sw t0, 27*4(sp)
+    la t0, syscall_entry
     csrw mepc, t0
 
 syscall_handler_end:
     ret
 
+##############################
+#Runs a syscall after returning from the interrupt handler. The kernel keeps
+#track of when a task is in a syscall so a task isn't killed while it holds
+#any of the kernel's locks. a0-a3 hold the syscall params, t0 holds the
+#address of the syscall and ra is where the syscall returns to.
+##############################
+syscall_entry:
+    addi sp, sp, -32
+    sw ra, 28(sp)
+    sw s1, 24(sp)
+    sw a0, 0(sp)
+    sw a1, 4(sp)
+    sw a2, 8(sp)
+    sw a3, 12(sp)
+    mv s1, t0
+
+    call enter_syscall
+    lw a0, 0(sp)
+    lw a1, 4(sp)
+    lw a2, 8(sp)
+    lw a3, 12(sp)
+
+    jalr s1
+
+    #Keep the return value, which can be 64 bits
+    sw a0, 0(sp)
+    sw a1, 4(sp)
+    call exit_syscall
+    lw a0, 0(sp)
+    lw a1, 4(sp)
+
+    lw s1, 24(sp)
+    lw ra, 28(sp)
+    addi sp, sp, 32
+    ret
+
 .align 2
 syscall_addr_table:
     .word sys_exit               # 0
@@ -68,5 +128,25 @@ syscall_addr_table:
     .word sys_ipc_get_message    # 10
     .word sys_get_heap_remaining # 11
     .word sys_interrupt_register # 12
+    .word sys_task_resume        # 13
+    .word sys_task_deadline_misses # 14
+    .word sys_mutex_lock         # 15
+    .word sys_mutex_unlock       # 16
+    .word sys_sem_take           # 17
+    .word sys_task_list          # 18
+    .word sys_task_stack_high_water# 19
+    .word sys_task_suspend       # 20
+    .word sys_task_kill          # 21
+    .word sys_task_cpu_usage     # 22
+    .word sys_timer_create       # 23
+    .word sys_timer_start        # 24
+    .word sys_timer_stop         # 25
+    .word sys_timer_change_period# 26
+    .word sys_timer_delete       # 27
+    .word sys_sleep_until        # 28
+    .word sys_get_ticks          # 29
+    .word sys_get_tick_hz        # 30
+    .word sys_event_wait         # 31
+    .word sys_cond_wait          # 32
 
 .option pop
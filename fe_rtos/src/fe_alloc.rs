@@ -1,6 +1,7 @@
 extern crate alloc;
 
 use crate::spinlock::Spinlock;
+use crate::task;
 use core::alloc::{GlobalAlloc, Layout};
 use core::ptr::null_mut;
 use fe_osi::allocator::LayoutFFI;
@@ -69,7 +70,18 @@ fn get_bit_mask(bit: usize, blocks_remaining: usize, cur_block: &mut usize) -> u
     }
 }
 
+//Tasks call the allocator directly instead of through a syscall. A task killed
+//while it held ALLOC_LOCK would keep every other task from allocating, so the
+//allocator counts as a syscall and the task isn't stopped until it's done.
 pub(crate) unsafe fn alloc(layout: LayoutFFI) -> *mut u8 {
+    task::as_syscall(|| alloc_blocks(layout))
+}
+
+pub(crate) unsafe fn dealloc(ptr: *mut u8, layout: LayoutFFI) {
+    task::as_syscall(|| free_blocks(ptr, layout));
+}
+
+unsafe fn alloc_blocks(layout: LayoutFFI) -> *mut u8 {
     //If HEAP points to zero, we need to initialize the heap
     ALLOC_LOCK.take();
     if (HEAP as usize) == 0 {
@@ -134,7 +146,7 @@ pub(crate) unsafe fn alloc(layout: LayoutFFI) -> *mut u8 {
     data_ptr
 }
 
-pub(crate) unsafe fn dealloc(ptr: *mut u8, layout: LayoutFFI) {
+unsafe fn free_blocks(ptr: *mut u8, layout: LayoutFFI) {
     //Determine the block that the pointer belongs to
     let start_block = (ptr as usize - HEAP as usize) / BLOCK_SIZE;
     let mut cur_block = start_block;
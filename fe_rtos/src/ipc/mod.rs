@@ -10,12 +10,13 @@ use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use fe_osi::semaphore::Semaphore;
+use fe_osi::sync::RawMutex;
 
 pub(crate) struct TopicRegistry {
     pub(crate) topic_lookup: BTreeMap<String, Topic>,
 }
 
-pub(crate) static mut TOPIC_REGISTERY_LOCK: Semaphore = Semaphore::new_mutex();
+pub(crate) static mut TOPIC_REGISTERY_LOCK: RawMutex = RawMutex::new();
 pub(crate) static mut TOPIC_REGISTERY: TopicRegistry = TopicRegistry {
     topic_lookup: BTreeMap::new(),
 };
@@ -45,6 +46,13 @@ impl TopicRegistry {
             .and_then(|topic| topic.remove_subscriber(pid))
     }
 
+    //Unsubscribes the task with the given pid from every topic
+    pub(crate) fn unsubscribe_task(&mut self, pid: usize) {
+        for topic in self.topic_lookup.values_mut() {
+            topic.remove_subscriber(pid);
+        }
+    }
+
     pub(crate) fn get_ipc_message(&mut self, msg_topic: &str) -> Option<Vec<u8>> {
         let cur_pid: usize = unsafe { get_cur_task().pid };
 
@@ -23,6 +23,7 @@ pub mod ipc;
 mod spinlock;
 pub mod syscall;
 pub mod task;
+pub mod timer;
 
 use fe_alloc::KernelAllocator;
 
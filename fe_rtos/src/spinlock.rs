@@ -1,6 +1,6 @@
 use crate::task::get_cur_task;
 use core::sync::atomic::{AtomicUsize, Ordering};
-use fe_osi::r#yield;
+use fe_osi::sleep;
 
 pub(crate) struct Spinlock {
     count: AtomicUsize,
@@ -31,7 +31,9 @@ impl Spinlock {
                 break;
             }
 
-            r#yield();
+            //Sleep instead of yielding so that a lower priority task holding
+            //the lock gets a chance to run and give it back
+            sleep(0);
         }
     }
 
@@ -4,14 +4,19 @@ use crate::arch;
 use crate::fe_alloc;
 use crate::ipc;
 use crate::task;
+use crate::timer;
 use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::ptr::{null, null_mut};
 use cstr_core::{c_char, CStr};
 use fe_osi::allocator::LayoutFFI;
 use fe_osi::ipc::Message;
 use fe_osi::semaphore::Semaphore;
+use fe_osi::sync::{Condvar, EventGroup, EventWait, RawMutex};
+use fe_osi::task::{CpuUsage, SpawnedTask, TaskAttributes, TaskEntry, TaskInfo};
+use fe_osi::timer::TimerCallback;
 
 //For the linker to link the syscalls, a function in this
 //file must be called from elsewhere...
@@ -20,14 +25,7 @@ pub fn link_syscalls() {}
 #[no_mangle]
 extern "C" fn sys_exit() -> usize {
     unsafe {
-        ipc::TOPIC_REGISTERY_LOCK.with_lock(|| {
-            let topics: Vec<String> = ipc::TOPIC_REGISTERY.topic_lookup.keys().cloned().collect();
-            for topic in topics {
-                ipc::TOPIC_REGISTERY.unsubscribe_from_topic(&topic);
-            }
-        });
-
-        task::get_cur_task().give();
+        task::release_task(task::get_cur_task());
 
         while !task::remove_task() {
             sys_yield();
@@ -38,16 +36,50 @@ extern "C" fn sys_exit() -> usize {
 }
 
 #[no_mangle]
-extern "C" fn sys_sleep(ms32: u32) -> usize {
-    let ms: u64 = ms32 as u64;
+extern "C" fn sys_sleep(ms: u32) -> usize {
+    let ticks = task::ms_to_ticks(ms as u64);
+
+    while !task::sleep(ticks) {
+        sys_yield();
+    }
+
+    0
+}
+
+//Sleeps until the tick count reaches tick. Returns 1 without sleeping if it
+//has already passed tick.
+#[no_mangle]
+extern "C" fn sys_sleep_until(tick: u64) -> usize {
+    let now = task::get_ticks();
+    if tick < now {
+        return 1;
+    }
+
+    //Reaching tick right on time isn't a miss, but there is nothing to wait
+    //for, so just give other tasks a turn
+    if tick == now {
+        sys_yield();
+        return 0;
+    }
 
-    while !task::sleep(ms) {
+    //A task wakes up once the tick count passes its wake up tick
+    while !task::sleep_until(tick - 1) {
         sys_yield();
     }
 
     0
 }
 
+#[no_mangle]
+extern "C" fn sys_get_ticks() -> u64 {
+    task::get_ticks()
+}
+
+#[no_mangle]
+extern "C" fn sys_get_tick_hz() -> usize {
+    task::get_tick_hz()
+}
+
 #[no_mangle]
 extern "C" fn sys_alloc(layout: LayoutFFI) -> *mut u8 {
     unsafe { fe_alloc::alloc(layout) }
@@ -64,45 +96,397 @@ extern "C" fn sys_dealloc(ptr: *mut u8, layout: LayoutFFI) -> usize {
 
 #[no_mangle]
 extern "C" fn sys_block(sem: *const Semaphore) -> usize {
-    while !task::block(sem) {
+    block_until(sem, None);
+    0
+}
+
+//Takes the semaphore, blocking for at most timeout ms. A timeout of 0 only
+//tries to take the semaphore once. Returns 0 if the semaphore was taken.
+#[no_mangle]
+extern "C" fn sys_sem_take(sem: *const Semaphore, timeout: u32) -> usize {
+    if take_sem(unsafe { &*sem }, timeout) {
+        0
+    } else {
+        1
+    }
+}
+
+//Takes the semaphore like sys_sem_take. Syscalls use this instead of
+//Semaphore::take to wait for a semaphore, since it gives up if the task is
+//killed. Returns false if the semaphore wasn't taken.
+fn take_sem(sem: &Semaphore, timeout: u32) -> bool {
+    let deadline = timeout_to_deadline(timeout);
+
+    loop {
+        if sem.try_take() {
+            return true;
+        }
+
+        if timeout == 0 || task::should_stop_waiting(deadline) {
+            return false;
+        }
+
+        block_until(sem, deadline);
+    }
+}
+
+//Wakes up the first task waiting on the semaphore. This isn't in the system
+//call table, since it's called directly by Semaphore::give so that it works
+//from interrupt handlers.
+#[no_mangle]
+extern "C" fn sys_sem_wake(sem: *const Semaphore) -> usize {
+    task::wake_one(unsafe { &*sem });
+    0
+}
+
+//Blocks until the semaphore is available or the deadline has passed
+fn block_until(sem: *const Semaphore, deadline: Option<u64>) {
+    while !task::block(sem, deadline) {
         unsafe {
-            if (*sem).is_available() {
+            if (*sem).is_available() || task::should_stop_waiting(deadline) {
+                break;
+            } else {
+                sys_yield();
+            }
+        }
+    }
+}
+
+#[no_mangle]
+extern "C" fn sys_event_wait(
+    group: *const EventGroup,
+    wait: *mut EventWait,
+    timeout: u32,
+) -> usize {
+    let group: &EventGroup = unsafe { &*group };
+    let wait: &mut EventWait = unsafe { &mut *wait };
+    let deadline = timeout_to_deadline(timeout);
+
+    loop {
+        if let Some(bits) = group.raw_try_wait(wait.mask, wait.wait_for_all, wait.clear_on_exit) {
+            wait.bits = bits;
+            return 0;
+        }
+
+        if timeout == 0 || task::should_stop_waiting(deadline) {
+            wait.bits = group.get();
+            return 1;
+        }
+
+        while !task::wait_for_events(group, wait.mask, wait.wait_for_all, deadline) {
+            if group.raw_is_met(wait.mask, wait.wait_for_all) || task::should_stop_waiting(deadline)
+            {
                 break;
             } else {
                 sys_yield();
             }
         }
     }
+}
+
+//Wakes up the tasks waiting on the event group whose flags are now set. Like
+//sys_sem_wake, this isn't in the system call table so that EventGroup::set
+//works from interrupt handlers.
+#[no_mangle]
+extern "C" fn sys_event_wake(group: *const EventGroup) -> usize {
+    task::wake_events(unsafe { &*group });
+    0
+}
+
+//Waits until the Condvar is notified after its notification count was seq,
+//for at most timeout ms. Returns 0 if it was notified.
+#[no_mangle]
+extern "C" fn sys_cond_wait(cond: *const Condvar, seq: usize, timeout: u32) -> usize {
+    let cond: &Condvar = unsafe { &*cond };
+    let deadline = timeout_to_deadline(timeout);
+    let notified = || cond.raw_seq() != seq;
+
+    loop {
+        if notified() {
+            return 0;
+        }
+
+        if timeout == 0 || task::should_stop_waiting(deadline) {
+            return 1;
+        }
+
+        while !task::wait_in_queue(cond.raw_waiters(), deadline, notified) {
+            if notified() || task::should_stop_waiting(deadline) {
+                break;
+            } else {
+                sys_yield();
+            }
+        }
+    }
+}
+
+//Wakes up one or all of the tasks waiting on the Condvar. Like sys_sem_wake,
+//this is called directly instead of through the system call table.
+#[no_mangle]
+extern "C" fn sys_cond_wake(cond: *const Condvar, all: bool) -> usize {
+    let waiters = unsafe { (*cond).raw_waiters() };
+
+    if all {
+        task::wake_all(waiters);
+    } else {
+        task::wake_one(waiters);
+    }
+
+    0
+}
+
+//Converts a timeout in milliseconds into a deadline in ticks
+fn timeout_to_deadline(timeout: u32) -> Option<u64> {
+    if timeout == fe_osi::WAIT_FOREVER {
+        None
+    } else {
+        Some(task::get_ticks() + task::ms_to_ticks(timeout as u64))
+    }
+}
+
+#[no_mangle]
+extern "C" fn sys_mutex_lock(mutex: *const RawMutex, timeout: u32) -> usize {
+    let mutex: &RawMutex = unsafe { &*mutex };
+    let cur_task = unsafe { task::get_cur_task() };
+    let deadline = timeout_to_deadline(timeout);
+
+    loop {
+        if mutex.raw_try_lock(cur_task.pid) {
+            cur_task.lock_mutex();
+            return 0;
+        }
+
+        if timeout == 0 || task::should_stop_waiting(deadline) {
+            return 1;
+        }
+
+        //Make sure the task holding the mutex can't be kept from running by
+        //a task with a lower priority than us
+        if let Some(owner) = mutex.owner().and_then(task::get_task) {
+            owner.inherit_priority(cur_task.priority());
+        }
+
+        block_until(mutex.raw_semaphore(), deadline);
+    }
+}
+
+#[no_mangle]
+extern "C" fn sys_mutex_unlock(mutex: *const RawMutex) -> usize {
+    let mutex: &RawMutex = unsafe { &*mutex };
+    let cur_task = unsafe { task::get_cur_task() };
+
+    if !mutex.raw_unlock(cur_task.pid) {
+        return 1;
+    }
+
+    //If our priority was raised, a higher priority task is waiting for
+    //the mutex, so let it run
+    if cur_task.unlock_mutex() {
+        sys_yield();
+    }
+
     0
 }
 
 #[no_mangle]
 extern "C" fn sys_task_spawn(
-    stack_size: usize,
-    entry_point: *const u32,
-    parameter: *mut u32,
-) -> *const Semaphore {
-    let sem = Arc::new(Semaphore::new(0));
-    let task_info = Box::new(task::NewTaskInfo {
-        ep: entry_point,
-        param: parameter,
-        sem: Arc::clone(&sem),
-    });
+    attributes: *const TaskAttributes,
+    entry: *mut TaskEntry,
+) -> SpawnedTask {
+    let attributes: &TaskAttributes = unsafe { &*attributes };
+
+    //A smaller stack can't hold the task's initial context, so setting it
+    //up would write past the stack
+    if attributes.stack_size < task::MIN_STACK_SIZE {
+        drop(unsafe { Box::from_raw(entry) });
+        return SpawnedTask {
+            sem: null(),
+            pid: 0,
+        };
+    }
 
-    unsafe {
+    let name = if attributes.name.is_null() {
+        None
+    } else {
+        unsafe { CStr::from_ptr(attributes.name) }
+            .to_str()
+            .ok()
+            .map(String::from)
+    };
+    let sem = Arc::new(Semaphore::new(0));
+    let options = task::TaskOptions {
+        name,
+        priority: attributes.priority.min(task::MAX_PRIORITY),
+        time_slice: attributes.time_slice.max(1),
+        suspended: attributes.suspended,
+        period: attributes.period,
+        //A task's deadline defaults to the end of its period
+        deadline: match attributes.deadline {
+            0 => attributes.period,
+            deadline => deadline.min(attributes.period),
+        },
+        //The task keeps its closure and exit semaphore from the start, so a
+        //task that is killed before it runs still gets cleaned up
+        task_info: Some(Box::new(task::NewTaskInfo {
+            entry: Some(*unsafe { Box::from_raw(entry as *mut TaskEntry) }),
+            sem: Arc::clone(&sem),
+        })),
+    };
+
+    let new_task = unsafe {
         task::add_task(
-            stack_size,
+            attributes.stack_size,
             task::new_task_helper as *const usize,
-            Box::into_raw(task_info) as *mut usize,
-        );
+            null_mut(),
+            options,
+        )
+    };
+
+    SpawnedTask {
+        sem: Arc::into_raw(sem),
+        pid: new_task.pid,
+    }
+}
+
+#[no_mangle]
+extern "C" fn sys_task_resume(pid: usize) -> usize {
+    if task::resume_task(pid) {
+        0
+    } else {
+        1
+    }
+}
+
+#[no_mangle]
+extern "C" fn sys_task_suspend(pid: usize) -> usize {
+    if task::suspend_task(pid) {
+        0
+    } else {
+        1
+    }
+}
+
+#[no_mangle]
+extern "C" fn sys_task_kill(pid: usize) -> usize {
+    //A task killing itself is the same as it exiting
+    if pid == unsafe { task::get_cur_task().pid } {
+        return sys_exit();
+    }
+
+    if task::kill_task(pid) {
+        0
+    } else {
+        1
+    }
+}
+
+#[no_mangle]
+extern "C" fn sys_task_deadline_misses(pid: usize, misses: *mut usize) -> usize {
+    match task::get_task(pid) {
+        Some(task) => {
+            unsafe {
+                *misses = task.deadline_misses();
+            }
+            0
+        }
+        None => 1,
     }
+}
+
+#[no_mangle]
+extern "C" fn sys_task_stack_high_water(pid: usize, high_water: *mut usize) -> usize {
+    match task::get_task(pid) {
+        Some(task) => {
+            unsafe {
+                *high_water = task.stack_high_water();
+            }
+            0
+        }
+        None => 1,
+    }
+}
+
+#[no_mangle]
+extern "C" fn sys_task_cpu_usage(pid: usize, usage: *mut CpuUsage) -> usize {
+    match task::cpu_usage(pid) {
+        Some(task_usage) => {
+            unsafe {
+                *usage = task_usage;
+            }
+            0
+        }
+        None => 1,
+    }
+}
+
+//Stores a snapshot of every task in the Vec list points to
+#[no_mangle]
+extern "C" fn sys_task_list(list: *mut u32) -> usize {
+    let list = unsafe { &mut *(list as *mut Vec<TaskInfo>) };
+    *list = task::task_list();
+    0
+}
 
-    Arc::into_raw(sem)
+#[no_mangle]
+extern "C" fn sys_timer_create(
+    period: u32,
+    periodic: bool,
+    callback: *mut u32,
+    id: *mut usize,
+) -> usize {
+    let callback = *unsafe { Box::from_raw(callback as *mut TimerCallback) };
+
+    if period == 0 {
+        return 1;
+    }
+
+    unsafe {
+        *id = timer::create_timer(task::ms_to_ticks(period as u64), periodic, callback);
+    }
+    0
+}
+
+#[no_mangle]
+extern "C" fn sys_timer_start(id: usize) -> usize {
+    if timer::start_timer(id) {
+        0
+    } else {
+        1
+    }
+}
+
+#[no_mangle]
+extern "C" fn sys_timer_stop(id: usize) -> usize {
+    if timer::stop_timer(id) {
+        0
+    } else {
+        1
+    }
+}
+
+#[no_mangle]
+extern "C" fn sys_timer_change_period(id: usize, period: u32) -> usize {
+    if period != 0 && timer::change_timer_period(id, task::ms_to_ticks(period as u64)) {
+        0
+    } else {
+        1
+    }
+}
+
+#[no_mangle]
+extern "C" fn sys_timer_delete(id: usize) -> usize {
+    if timer::delete_timer(id) {
+        0
+    } else {
+        1
+    }
 }
 
 #[no_mangle]
 extern "C" fn sys_yield() -> usize {
     unsafe {
+        //A task that yields gives up the rest of its time slice
+        task::get_cur_task().end_slice();
         task::do_context_switch();
     }
 
@@ -194,9 +578,10 @@ extern "C" fn sys_ipc_get_message(c_topic: *const c_char, block: bool) -> Messag
             return get_null_message();
         }
 
-        if block {
-            sem_ref.unwrap().take();
-        } else if !sem_ref.unwrap().try_take() {
+        //Semaphore::take would be a nested syscall, which doesn't stop
+        //waiting if the task is killed
+        let timeout = if block { fe_osi::WAIT_FOREVER } else { 0 };
+        if !take_sem(sem_ref.unwrap(), timeout) {
             return get_null_message();
         }
 
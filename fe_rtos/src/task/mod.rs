@@ -4,23 +4,32 @@ extern crate crossbeam_queue;
 mod schedule;
 mod task_state;
 mod tick;
+mod usage;
+mod wait_queue;
 
 use crate::arch;
+use crate::ipc;
 use crate::spinlock::Spinlock;
 use crate::syscall;
-use crate::task::schedule::{RoundRobin, Scheduler};
+pub use crate::task::schedule::{EarliestDeadlineFirst, PriorityScheduler, RoundRobin, Scheduler};
 use crate::task::task_state::{TaskState, TaskStateStruct};
 use crate::task::tick::TickCounter;
+use crate::task::usage::UsageCounter;
+use crate::task::wait_queue::WaitLinks;
+use crate::timer;
 use alloc::boxed::Box;
-use alloc::collections::LinkedList;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::mem::size_of;
-use core::ptr::null_mut;
+use core::ptr::{null, null_mut};
 use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use crossbeam_queue::SegQueue;
 use fe_osi::semaphore::Semaphore;
+use fe_osi::sync::EventGroup;
+use fe_osi::task::{CpuUsage, TaskEntry, TaskInfo, IDLE_PID};
 
 #[repr(C)]
 union StackPtr {
@@ -29,8 +38,9 @@ union StackPtr {
     num: usize,
 }
 
+/// A task managed by the kernel.
 #[repr(C)]
-pub(crate) struct Task {
+pub struct Task {
     //Stack pointer
     sp: StackPtr,
     //Reference to the stack canary
@@ -40,33 +50,319 @@ pub(crate) struct Task {
     //It needs to be a smart type to easily allow for deallocation of the stack
     //if a task is deleted
     dynamic_stack: Vec<usize>,
-    //Stores the param of the task if it was created with the task_spawn syscall
-    //This will be a raw pointer to a Box that will need to be freed
+    //The lowest address of the stack and its size in words
+    stack_bottom: *const usize,
+    stack_size: usize,
+    //Stores the exit semaphore of the task if it was created with the
+    //task_spawn syscall
     task_info: Option<Box<NewTaskInfo>>,
     state: TaskStateStruct,
     pub(crate) pid: usize,
+    //The priority the task is scheduled with. This is raised above
+    //base_priority while the task holds a Mutex a higher priority task wants.
+    priority: AtomicUsize,
+    base_priority: usize,
+    mutexes_held: AtomicUsize,
+    pub(crate) name: Option<String>,
+    //The number of ticks the task can run before a task of the same
+    //priority gets a turn
+    time_slice: usize,
+    slice_left: AtomicUsize,
+    //The period and relative deadline of a periodic task in ticks.
+    //Both are 0 if the task is not periodic.
+    period: usize,
+    deadline: usize,
+    deadline_misses: AtomicUsize,
+    //How long the task has spent running
+    usage: UsageCounter,
+    //Links the task into the queues it waits in while asleep or blocked
+    wait: WaitLinks,
+    //How many syscalls deep the task is. A task in a syscall, or in the
+    //allocator, may hold the kernel's locks, so it can't be stopped until it
+    //leaves the kernel.
+    syscall_depth: AtomicUsize,
+    //Set when the task is killed. A task killed in a syscall exits once it
+    //leaves the kernel.
+    killed: AtomicBool,
 }
 
 unsafe impl Send for Task {}
 unsafe impl Sync for Task {}
 
 impl Task {
+    fn new(
+        sp: StackPtr,
+        canary: &'static usize,
+        stack: Vec<usize>,
+        stack_bottom: *const usize,
+        stack_size: usize,
+        options: TaskOptions,
+    ) -> Self {
+        let state = if options.suspended {
+            TaskState::Suspended
+        } else {
+            TaskState::Runnable
+        };
+
+        Task {
+            sp,
+            canary,
+            dynamic_stack: stack,
+            stack_bottom,
+            stack_size,
+            task_info: options.task_info,
+            state: TaskStateStruct::with_state(state),
+            pid: get_new_pid(),
+            priority: AtomicUsize::new(options.priority),
+            base_priority: options.priority,
+            mutexes_held: AtomicUsize::new(0),
+            name: options.name,
+            time_slice: options.time_slice,
+            slice_left: AtomicUsize::new(0),
+            period: options.period,
+            deadline: options.deadline,
+            deadline_misses: AtomicUsize::new(0),
+            usage: UsageCounter::new(),
+            wait: WaitLinks::new(),
+            syscall_depth: AtomicUsize::new(0),
+            killed: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the most words of its stack the task has used so far.
+    pub fn stack_high_water(&self) -> usize {
+        if self.stack_bottom.is_null() {
+            return 0;
+        }
+
+        //Stacks grow down from the top, so the words at the bottom that still
+        //have the paint on them have never been used. The bottom word holds
+        //the canary instead of paint.
+        let unused = (1..self.stack_size)
+            .take_while(|&i| unsafe { *self.stack_bottom.add(i) } == STACK_PAINT)
+            .count();
+        self.stack_size - 1 - unused
+    }
+
+    //Returns a snapshot of the task
+    pub(crate) fn info(&self) -> TaskInfo {
+        //The state is only briefly in use, so wait for it to be free
+        let state = loop {
+            match self.state.try_get() {
+                Some(state) => break state,
+                None => fe_osi::sleep(0),
+            };
+        };
+
+        TaskInfo {
+            pid: self.pid,
+            name: self.name.clone(),
+            state: state.into(),
+            stack_size: self.stack_size,
+            stack_high_water: self.stack_high_water(),
+            priority: self.priority(),
+            cpu_usage: cpu_usage_of(&self.usage),
+        }
+    }
+
+    /// Returns how long the task has spent running.
+    pub fn cpu_usage(&self) -> CpuUsage {
+        cpu_usage_of(&self.usage)
+    }
+
+    //Returns true if the task has written past the bottom of its stack
+    pub(crate) fn stack_overflowed(&self) -> bool {
+        *self.canary != STACK_CANARY
+    }
+
     pub(crate) fn give(&self) {
         if let Some(task_info) = &self.task_info {
             task_info.sem.give();
         }
     }
+
+    /// Returns the pid of the task.
+    pub fn pid(&self) -> usize {
+        self.pid
+    }
+
+    /// Returns the priority of the task. Higher numbers have higher priority.
+    pub fn priority(&self) -> usize {
+        self.priority.load(Ordering::SeqCst)
+    }
+
+    /// Returns the period of the task in ticks, or 0 if it is not periodic.
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    /// Returns the relative deadline of the task in ticks, or 0 if it is not periodic.
+    pub fn deadline(&self) -> usize {
+        self.deadline
+    }
+
+    /// Returns the number of deadlines the task has missed.
+    pub fn deadline_misses(&self) -> usize {
+        self.deadline_misses.load(Ordering::SeqCst)
+    }
+
+    //Raises the task's priority to at least the given priority because a task
+    //with that priority is waiting on a Mutex this task holds
+    pub(crate) fn inherit_priority(&self, priority: usize) {
+        if self.priority.fetch_max(priority, Ordering::SeqCst) < priority {
+            priority_changed(self.pid);
+        }
+    }
+
+    pub(crate) fn lock_mutex(&self) {
+        self.mutexes_held.fetch_add(1, Ordering::SeqCst);
+    }
+
+    //Keeps track of the task releasing a Mutex. Once the task doesn't hold any
+    //more Mutexes, it goes back to its original priority.
+    //Returns true if the task's priority was lowered.
+    pub(crate) fn unlock_mutex(&self) -> bool {
+        if self.mutexes_held.fetch_sub(1, Ordering::SeqCst) == 1
+            && self.priority.swap(self.base_priority, Ordering::SeqCst) != self.base_priority
+        {
+            priority_changed(self.pid);
+            return true;
+        }
+
+        false
+    }
+
+    pub(crate) fn miss_deadline(&self) {
+        self.deadline_misses.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns true if the task can be scheduled.
+    /// A sleeping or blocked task that has been woken up by the tick or by
+    /// its semaphore being given will be made runnable again.
+    pub fn is_runnable(&self) -> bool {
+        //We want to default to Runnable because if a task is in a transition state,
+        //it should be scheduled so it can finish transitioning.
+        let runnable = match self.state.try_get().unwrap_or(TaskState::Runnable) {
+            TaskState::Runnable => return true,
+            TaskState::Asleep | TaskState::Blocking | TaskState::WaitingForEvents => {
+                self.wait.is_woken()
+            }
+            TaskState::Suspended | TaskState::Zombie => false,
+        };
+
+        if runnable {
+            //If this fails, the task is already changing its state itself
+            self.state.try_set(TaskState::Runnable);
+        }
+
+        runnable
+    }
+
+    /// Gives the task a new time slice. This should be called when the task is
+    /// picked to run after another task.
+    pub fn start_slice(&self) {
+        self.slice_left.store(self.time_slice, Ordering::SeqCst);
+    }
+
+    //Gives up the rest of the task's time slice
+    pub(crate) fn end_slice(&self) {
+        self.slice_left.store(0, Ordering::SeqCst);
+    }
+
+    /// Returns true if the task has not used up its time slice.
+    pub fn has_slice_left(&self) -> bool {
+        self.slice_left.load(Ordering::SeqCst) > 0
+    }
+
+    //Uses up a tick of the task's time slice
+    fn use_tick(&self) {
+        let _ = self
+            .slice_left
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                left.checked_sub(1)
+            });
+    }
+}
+
+//The attributes of a task that is being created
+pub(crate) struct TaskOptions {
+    pub name: Option<String>,
+    pub priority: usize,
+    pub time_slice: usize,
+    pub suspended: bool,
+    pub period: usize,
+    pub deadline: usize,
+    pub task_info: Option<Box<NewTaskInfo>>,
 }
 
 pub(crate) struct NewTaskInfo {
-    pub ep: *const u32,
-    pub param: *mut u32,
+    //The closure the task runs. This is taken out when the task starts.
+    pub entry: Option<TaskEntry>,
     pub sem: Arc<Semaphore>,
 }
 
 pub(crate) const STACK_CANARY: usize = 0xC0DE5AFE;
+//The number of ticks CPU usage percentages are measured over
+const CPU_USAGE_WINDOW: u64 = 1000;
+//Every word of a new stack is set to this so we can tell how much was used
+const STACK_PAINT: usize = 0xA5A5A5A5;
 /// The default and recommended stack size for a task.
-pub const DEFAULT_STACK_SIZE: usize = 1536;
+pub const DEFAULT_STACK_SIZE: usize = fe_osi::task::DEFAULT_STACK_SIZE;
+/// The smallest stack size in words a task can be created with.
+pub const MIN_STACK_SIZE: usize = fe_osi::task::MIN_STACK_SIZE;
+/// The number of task priority levels.
+/// Priorities range from 0, the lowest, to `MAX_PRIORITY`.
+pub const NUM_PRIORITIES: usize = 8;
+/// The highest priority a task can have. The kernel task runs at this priority.
+pub const MAX_PRIORITY: usize = NUM_PRIORITIES - 1;
+/// The default priority of a task.
+pub const DEFAULT_PRIORITY: usize = fe_osi::task::DEFAULT_PRIORITY;
+/// The default number of ticks a task runs before another task of the same
+/// priority gets to run.
+pub const DEFAULT_TIME_SLICE: usize = fe_osi::task::DEFAULT_TIME_SLICE;
+/// The tick frequency assumed until the scheduler is started.
+pub const DEFAULT_TICK_HZ: usize = 1000;
+
+/// How fast the scheduler's tick runs.
+///
+/// #Examples
+/// ```
+/// //An 80MHz core with a 1ms tick
+/// let config = TickConfig::new(80_000_000, 1000);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct TickConfig {
+    /// The frequency of the clock the tick timer counts. This is the core
+    /// clock for SysTick and the timebase frequency of mtime on RISC-V.
+    pub clock_hz: usize,
+    /// The number of ticks in a second.
+    pub tick_hz: usize,
+}
+
+impl TickConfig {
+    /// Creates a TickConfig for a timer counting a clock_hz clock that
+    /// should tick tick_hz times a second.
+    pub const fn new(clock_hz: usize, tick_hz: usize) -> Self {
+        TickConfig { clock_hz, tick_hz }
+    }
+
+    /// Returns the number of clock cycles in a tick, or an error if the tick
+    /// timer can't count that many.
+    pub fn reload_val(&self) -> Result<usize, &'static str> {
+        match self.clock_hz.checked_div(self.tick_hz) {
+            Some(0) | None => Err("Tick rate must be between 1 and the clock rate."),
+            Some(reload_val) if (..=arch::MAX_TICK_RELOAD).contains(&reload_val) => Ok(reload_val),
+            Some(_) => Err("Tick rate is too slow for the tick timer."),
+        }
+    }
+
+    /// Returns the number of ticks in a second the timer really runs at,
+    /// since the clock rate may not divide evenly into ticks.
+    pub fn actual_tick_hz(&self) -> Result<usize, &'static str> {
+        self.reload_val()
+            .map(|reload_val| self.clock_hz / reload_val)
+    }
+}
 
 static mut KERNEL_STACK: [usize; DEFAULT_STACK_SIZE] = [0; DEFAULT_STACK_SIZE];
 static mut TICKS: TickCounter = TickCounter::new();
@@ -74,18 +370,55 @@ static mut PLACEHOLDER_TASK: Task = Task {
     sp: StackPtr { num: 0 },
     canary: &STACK_CANARY,
     dynamic_stack: Vec::new(),
+    stack_bottom: null(),
+    stack_size: 0,
     task_info: None,
     state: TaskStateStruct::new(),
     pid: 0,
+    priority: AtomicUsize::new(0),
+    base_priority: 0,
+    mutexes_held: AtomicUsize::new(0),
+    name: None,
+    time_slice: 0,
+    slice_left: AtomicUsize::new(0),
+    period: 0,
+    deadline: 0,
+    deadline_misses: AtomicUsize::new(0),
+    usage: UsageCounter::new(),
+    wait: WaitLinks::new(),
+    syscall_depth: AtomicUsize::new(0),
+    killed: AtomicBool::new(false),
 };
 static PUSHING_TASK: AtomicBool = AtomicBool::new(false);
-static mut SCHEDULER: RoundRobin = RoundRobin::new();
+static mut SCHEDULER: Option<Box<dyn Scheduler>> = None;
 static mut KERNEL_TASK: Option<Arc<Task>> = None;
+static mut STACK_OVERFLOW_HOOK: Option<fn(usize, Option<&str>)> = None;
+static RESET_ON_STACK_OVERFLOW: AtomicBool = AtomicBool::new(false);
+//CPU usage accounting. Time the kernel spends running because no other task
+//is runnable counts as idle time rather than the kernel's own.
+static IDLE_USAGE: UsageCounter = UsageCounter::new();
+static mut RUNNING_IDLE: bool = false;
+static mut LAST_SWITCH_TICK: u64 = 0;
+static mut USAGE_WINDOW_START: u64 = 0;
+static USAGE_WINDOW_LEN: AtomicUsize = AtomicUsize::new(0);
+//The number of ticks in a second. This is set when the scheduler starts.
+static TICK_HZ: AtomicUsize = AtomicUsize::new(DEFAULT_TICK_HZ);
 static mut NEXT_TASK: Option<Arc<Task>> = None;
 static mut CUR_TASK: Option<&mut Task> = None;
 lazy_static! {
     static ref NEW_TASK_QUEUE: SegQueue<Arc<Task>> = SegQueue::new();
+    //The pids of tasks that have exited or been killed, to be cleaned up by the kernel
+    static ref REAP_QUEUE: SegQueue<usize> = SegQueue::new();
 }
+//Given whenever the kernel task has something to do
+static KERNEL_SEM: Semaphore = Semaphore::new(0);
+//Set by the scheduler when it stops a task that overflowed its stack
+static STACK_OVERFLOWED: AtomicBool = AtomicBool::new(false);
+//Held while the scheduler is being changed outside of the scheduler function
+static mut SCHEDULER_LOCK: Semaphore = Semaphore::new_mutex();
+//Every task that has not been cleaned up yet, by pid
+pub(crate) static mut TASK_LIST_LOCK: Semaphore = Semaphore::new_mutex();
+pub(crate) static mut TASK_LIST: BTreeMap<usize, Arc<Task>> = BTreeMap::new();
 
 unsafe fn get_cur_task_mut() -> &'static mut Task {
     match &mut CUR_TASK {
@@ -122,6 +455,25 @@ unsafe fn scheduler() {
         None => panic!("No valid KERNEL_TASK in scheduler"),
     };
 
+    //A task that overflowed its stack must not run again. The kernel will
+    //report it and clean up after it.
+    let cur_task = get_cur_task();
+    if cur_task.pid != default_task.pid && cur_task.stack_overflowed() {
+        cur_task.state.try_replace(TaskState::Zombie);
+        wait_queue::cancel(cur_task);
+        STACK_OVERFLOWED.store(true, Ordering::SeqCst);
+        KERNEL_SEM.give();
+    }
+
+    //Charge the time since the last context switch to whatever was running
+    let now = TICKS.get();
+    if RUNNING_IDLE {
+        IDLE_USAGE.add(now - LAST_SWITCH_TICK);
+    } else {
+        cur_task.usage.add(now - LAST_SWITCH_TICK);
+    }
+    LAST_SWITCH_TICK = now;
+
     //Make sure we don't accidentally drop a task
     let count = match &NEXT_TASK {
         Some(task) => Arc::strong_count(task),
@@ -132,12 +484,14 @@ unsafe fn scheduler() {
     }
 
     //Find the next task to run if there is one
-    match SCHEDULER.next() {
+    match SCHEDULER.as_mut().and_then(|scheduler| scheduler.next()) {
         Some(task) => {
             NEXT_TASK = Some(task);
+            RUNNING_IDLE = false;
         }
         None => {
             NEXT_TASK = Some(Arc::clone(&default_task));
+            RUNNING_IDLE = true;
         }
     }
 }
@@ -152,18 +506,120 @@ pub(crate) unsafe fn do_context_switch() {
     }
 }
 
+//Returns the number of ticks since the scheduler started
+pub(crate) fn get_ticks() -> u64 {
+    unsafe { TICKS.get() }
+}
+
+//Returns the number of ticks in a second
+pub(crate) fn get_tick_hz() -> usize {
+    TICK_HZ.load(Ordering::SeqCst)
+}
+
+//Converts milliseconds into ticks, rounding up so waiting that many ticks
+//takes at least as long
+pub(crate) fn ms_to_ticks(ms: u64) -> u64 {
+    (ms * get_tick_hz() as u64).div_ceil(1000)
+}
+
+//Returns true if the deadline in ticks has passed.
+//A deadline of None never passes.
+fn has_passed(deadline: Option<u64>) -> bool {
+    match deadline {
+        Some(ticks) => ticks < get_ticks(),
+        None => false,
+    }
+}
+
+//Returns true if the running task should stop waiting, either because the
+//deadline has passed or because it was killed and needs to leave the kernel
+pub(crate) fn should_stop_waiting(deadline: Option<u64>) -> bool {
+    has_passed(deadline) || must_exit(unsafe { get_cur_task() })
+}
+
+//Returns true if the task was killed and has to leave the kernel. Only the
+//task's outermost syscall gives up on what it's waiting for. The syscalls it
+//makes from inside the kernel take the kernel's locks, and it has to hold
+//those to clean up after itself.
+fn must_exit(task: &Task) -> bool {
+    task.killed.load(Ordering::SeqCst) && task.syscall_depth.load(Ordering::SeqCst) == 1
+}
+
+//Called by the syscall handler before a syscall runs
+#[no_mangle]
+pub(crate) unsafe extern "C" fn enter_syscall() {
+    get_cur_task().syscall_depth.fetch_add(1, Ordering::SeqCst);
+}
+
+//Called by the syscall handler after a syscall returns. A task that was
+//killed while it was in the kernel exits here, since it no longer holds any
+//of the kernel's locks.
+#[no_mangle]
+pub(crate) unsafe extern "C" fn exit_syscall() {
+    let cur_task = get_cur_task();
+
+    //The depth stays at 1 while the task cleans up, so the syscalls it makes
+    //don't end up back here
+    if must_exit(cur_task) {
+        release_task(cur_task);
+
+        while !remove_task() {
+            fe_osi::r#yield();
+        }
+    }
+
+    cur_task.syscall_depth.fetch_sub(1, Ordering::SeqCst);
+}
+
+//Runs f as if it were a syscall. Kernel code that tasks call directly, like
+//the allocator, takes the kernel's locks, so a task killed while it's running
+//f exits once f returns.
+pub(crate) fn as_syscall<R, F: FnOnce() -> R>(f: F) -> R {
+    unsafe {
+        enter_syscall();
+        let ret_val = f();
+        exit_syscall();
+
+        ret_val
+    }
+}
+
 pub(crate) unsafe extern "C" fn sys_tick() {
     TICKS.inc();
+    wait_queue::wake_sleepers(TICKS.get());
+    get_cur_task().use_tick();
     do_context_switch();
 }
 
+//Wakes up the first task waiting on the semaphore
+pub(crate) fn wake_one(sem: &Semaphore) {
+    wait_queue::wake_one(sem);
+}
+
+//Wakes up every task waiting on the semaphore
+pub(crate) fn wake_all(sem: &Semaphore) {
+    wait_queue::wake_all(sem);
+}
+
 //Puts the currently running thread to sleep for at least the specified number
 //of ticks
 pub(crate) fn sleep(sleep_ticks: u64) -> bool {
+    sleep_until(get_ticks() + sleep_ticks)
+}
+
+//Puts the currently running thread to sleep until the tick count passes wake_tick
+pub(crate) fn sleep_until(wake_tick: u64) -> bool {
     unsafe {
-        let ret_val = get_cur_task()
-            .state
-            .try_set(TaskState::Asleep(TICKS.get() + sleep_ticks));
+        let cur_task = get_cur_task();
+
+        wait_queue::sleep_until(cur_task, wake_tick);
+        //A task that was killed has to leave the kernel instead of sleeping
+        if must_exit(cur_task) {
+            wait_queue::cancel(cur_task);
+            return true;
+        }
+
+        let ret_val = cur_task.state.try_set(TaskState::Asleep);
         //Trigger a context switch and wait until that happens
         do_context_switch();
 
@@ -172,16 +628,66 @@ pub(crate) fn sleep(sleep_ticks: u64) -> bool {
 }
 
 //Has the currently running thread block until the semaphore it's blocking on
-//is available
-pub(crate) fn block(sem: *const Semaphore) -> bool {
+//is available or the deadline, if there is one, has passed
+pub(crate) fn block(sem: *const Semaphore, deadline: Option<u64>) -> bool {
+    unsafe { wait_in_queue(&*sem, deadline, || (*sem).is_available()) }
+}
+
+//Has the currently running thread wait in the semaphore's queue until it's
+//woken up or the deadline, if there is one, has passed. done is checked after
+//the thread is queued, so whatever it's waiting for can't be missed.
+pub(crate) fn wait_in_queue<F: Fn() -> bool>(
+    sem: &Semaphore,
+    deadline: Option<u64>,
+    done: F,
+) -> bool {
+    unsafe {
+        let cur_task = get_cur_task();
+
+        wait_queue::wait_on(cur_task, sem, deadline);
+        //If it happened before we were queued, nothing would wake us
+        if done() || must_exit(cur_task) {
+            wait_queue::cancel(cur_task);
+            return true;
+        }
+
+        let ret_val = cur_task.state.try_set(TaskState::Blocking);
+        do_context_switch();
+
+        ret_val
+    }
+}
+
+//Has the currently running thread wait until the flags in mask are set in the
+//event group or the deadline, if there is one, has passed
+pub(crate) fn wait_for_events(
+    group: &EventGroup,
+    mask: u32,
+    wait_for_all: bool,
+    deadline: Option<u64>,
+) -> bool {
     unsafe {
-        let ret_val = get_cur_task().state.try_set(TaskState::Blocking(sem));
+        let cur_task = get_cur_task();
+
+        wait_queue::wait_for_events(cur_task, group, mask, wait_for_all, deadline);
+        //If the flags were set before we were queued, nothing would wake us
+        if group.raw_is_met(mask, wait_for_all) || must_exit(cur_task) {
+            wait_queue::cancel(cur_task);
+            return true;
+        }
+
+        let ret_val = cur_task.state.try_set(TaskState::WaitingForEvents);
         do_context_switch();
 
         ret_val
     }
 }
 
+//Wakes up the tasks waiting on the event group whose flags are now set
+pub(crate) fn wake_events(group: &EventGroup) {
+    wait_queue::wake_events(group);
+}
+
 fn get_new_pid() -> usize {
     static PID: AtomicUsize = AtomicUsize::new(1);
     PID.fetch_add(1, Ordering::SeqCst)
@@ -191,32 +697,26 @@ pub(crate) unsafe fn add_task(
     stack_size: usize,
     entry_point: *const usize,
     param: *mut usize,
+    options: TaskOptions,
 ) -> Arc<Task> {
     let mut sp = StackPtr { num: 0 };
-    let stack = vec![0; stack_size];
+    let stack = vec![STACK_PAINT; stack_size];
     //Convert the adress of the first element of the vector into a ptr for the stack
     sp.ptr = &stack[0] as *const usize;
+    let stack_bottom = sp.ptr;
     let stack_size_bytes = size_of::<usize>() * stack_size;
 
     let canary: &'static usize = &*arch::set_canary(sp.ptr, stack_size_bytes);
     sp.ptr = arch::set_initial_stack(sp.ptr, stack_size_bytes, entry_point, param);
 
-    let new_task = Task {
+    register_task(Task::new(
         sp,
         canary,
-        dynamic_stack: stack,
-        task_info: None,
-        state: TaskStateStruct::new(),
-        pid: get_new_pid(),
-    };
-
-    let task_ref = Arc::new(new_task);
-
-    PUSHING_TASK.store(true, Ordering::SeqCst);
-    NEW_TASK_QUEUE.push(Arc::clone(&task_ref));
-    PUSHING_TASK.store(false, Ordering::SeqCst);
-
-    task_ref
+        stack,
+        stack_bottom,
+        stack_size,
+        options,
+    ))
 }
 
 pub(crate) unsafe fn add_task_static(
@@ -224,48 +724,336 @@ pub(crate) unsafe fn add_task_static(
     stack_size: usize,
     entry_point: *const usize,
     param: Option<*mut usize>,
+    options: TaskOptions,
 ) -> Arc<Task> {
     let mut sp = StackPtr {
         reference: stack_ptr,
     };
+    let stack_bottom = sp.ptr;
     let stack_size_bytes = size_of::<usize>() * stack_size;
     let param_ptr = match param {
         Some(p) => p as *mut usize,
         None => null_mut(),
     };
 
+    for i in 0..stack_size {
+        *(stack_bottom as *mut usize).add(i) = STACK_PAINT;
+    }
+
     let canary: &'static usize = &*arch::set_canary(sp.ptr, stack_size_bytes);
     sp.ptr = arch::set_initial_stack(sp.ptr, stack_size_bytes, entry_point, param_ptr);
 
-    let new_task = Task {
+    register_task(Task::new(
         sp,
         canary,
-        dynamic_stack: Vec::new(),
-        task_info: None,
-        state: TaskStateStruct::new(),
-        pid: get_new_pid(),
-    };
+        Vec::new(),
+        stack_bottom,
+        stack_size,
+        options,
+    ))
+}
 
+//Adds a new task to the task list and queues it up to be given to the scheduler
+unsafe fn register_task(new_task: Task) -> Arc<Task> {
     let task_ref = Arc::new(new_task);
 
+    TASK_LIST_LOCK.with_lock(|| {
+        TASK_LIST.insert(task_ref.pid, Arc::clone(&task_ref));
+    });
+
     PUSHING_TASK.store(true, Ordering::SeqCst);
     NEW_TASK_QUEUE.push(Arc::clone(&task_ref));
     PUSHING_TASK.store(false, Ordering::SeqCst);
+    KERNEL_SEM.give();
 
     task_ref
 }
 
+//Unsubscribes a task from its topics and gives its exit semaphore.
+//This needs to be done for every task before it is freed.
+pub(crate) fn release_task(task: &Task) {
+    unsafe {
+        ipc::TOPIC_REGISTERY_LOCK.with_lock(|| {
+            ipc::TOPIC_REGISTERY.unsubscribe_task(task.pid);
+        });
+    }
+
+    task.give();
+}
+
+/// Sets a function that is called with the pid and name of a task that
+/// overflowed its stack, before the task is cleaned up.
+/// The overflow may have damaged the heap, so the hook should avoid allocating.
+/// This should be called before starting the scheduler.
+pub fn set_stack_overflow_hook(hook: fn(usize, Option<&str>)) {
+    unsafe {
+        STACK_OVERFLOW_HOOK = Some(hook);
+    }
+}
+
+/// If reset is true, the system is reset when a task overflows its stack
+/// instead of only killing that task. A killed task's stack is still freed,
+/// which is best effort, since the overflow may have damaged the heap.
+pub fn reset_on_stack_overflow(reset: bool) {
+    RESET_ON_STACK_OVERFLOW.store(reset, Ordering::SeqCst);
+}
+
+//Reports a task that overflowed its stack and cleans up after it.
+//Freeing the overflowed stack is best effort. The allocator keeps track of
+//blocks apart from the blocks themselves, so freeing the stack is safe unless
+//the overflow reached that bookkeeping, and by then the heap is lost anyway.
+fn handle_stack_overflow(task: &Task) {
+    //Print without allocating in case the overflow damaged the heap
+    let mut digits: [u8; 20] = [0; 20];
+    let start_idx = fe_osi::usize_to_chars(&mut digits, task.pid);
+    fe_osi::print_msg("Stack overflow in task ");
+    fe_osi::print_msg(core::str::from_utf8(&digits[start_idx..]).unwrap());
+    fe_osi::print_msg("!\n");
+
+    if let Some(hook) = unsafe { STACK_OVERFLOW_HOOK } {
+        hook(task.pid, task.name.as_deref());
+    }
+
+    if RESET_ON_STACK_OVERFLOW.load(Ordering::SeqCst) {
+        unsafe {
+            arch::reset();
+        }
+    }
+
+    release_task(task);
+}
+
+//Lets a suspended task run again. A task that was suspended while it was
+//asleep goes back to sleep until its wake up tick.
+//Returns false if there is no suspended task with the given pid.
+pub(crate) fn resume_task(pid: usize) -> bool {
+    match get_task(pid) {
+        Some(task) => match task.state.try_get() {
+            Some(TaskState::Suspended) if wait_queue::is_sleeping(&task) => {
+                task.state.try_replace(TaskState::Asleep)
+            }
+            Some(TaskState::Suspended) => task.state.try_replace(TaskState::Runnable),
+            _ => false,
+        },
+        None => false,
+    }
+}
+
+//Stops a task from running until it is resumed. The timer task takes the
+//timer list's lock outside of a syscall, so like the kernel it can't be
+//suspended.
+//Returns false if there is no task with the given pid that can be suspended.
+pub(crate) fn suspend_task(pid: usize) -> bool {
+    let task = match get_task(pid) {
+        Some(task) if !is_kernel_task(pid) && !timer::is_timer_task(pid) => task,
+        _ => return false,
+    };
+
+    //The running task has to yield after it suspends itself
+    if pid == unsafe { get_cur_task().pid } {
+        return task.state.try_set(TaskState::Suspended);
+    }
+
+    unsafe {
+        //Interrupts are disabled so the task can't be woken between
+        //suspending it and taking it out of the queue it's blocked in
+        let state = arch::enter_critical();
+        let suspended = match task.state.try_get() {
+            Some(TaskState::Zombie) | None => false,
+            Some(old_state) => {
+                let suspended = task.state.try_replace(TaskState::Suspended);
+                //A suspended task can't be the one a semaphore or event group
+                //wakes up, so it gives up its place in the queue. Its syscall
+                //starts waiting again once it's resumed.
+                if suspended
+                    && matches!(old_state, TaskState::Blocking | TaskState::WaitingForEvents)
+                {
+                    wait_queue::withdraw(&task);
+                }
+
+                suspended
+            }
+        };
+        arch::exit_critical(state);
+
+        suspended
+    }
+}
+
+//How kill_task got a task to stop running
+enum Kill {
+    //The task is a zombie now and can be cleaned up right away
+    Now,
+    //The task is in a syscall and will exit once it leaves the kernel
+    OnSyscallExit,
+    //The task has already exited or been killed
+    AlreadyDead,
+    //The task's state is being changed, so try again
+    Busy,
+}
+
+//Tries to kill a task. Whether the task is in a syscall can only change
+//while it runs, so this is called with interrupts disabled.
+fn try_kill(task: &Task) -> Kill {
+    if task.killed.load(Ordering::SeqCst) {
+        return Kill::AlreadyDead;
+    }
+
+    let kill = match task.state.try_get() {
+        Some(TaskState::Zombie) => return Kill::AlreadyDead,
+        None => return Kill::Busy,
+        Some(_) if task.syscall_depth.load(Ordering::SeqCst) > 0 => Kill::OnSyscallExit,
+        Some(_) if task.state.try_replace(TaskState::Zombie) => Kill::Now,
+        Some(_) => return Kill::Busy,
+    };
+    task.killed.store(true, Ordering::SeqCst);
+
+    kill
+}
+
+//Stops a task for good and cleans up after it like sys_exit would.
+//A task that is in a syscall may hold the kernel's locks, so it's woken up
+//if it's waiting and exits once it leaves the kernel.
+//Returns false if there is no task with the given pid that can be killed.
+pub(crate) fn kill_task(pid: usize) -> bool {
+    let task = match get_task(pid) {
+        Some(task) if !is_kernel_task(pid) && !timer::is_timer_task(pid) => task,
+        _ => return false,
+    };
+
+    let kill = loop {
+        let kill = unsafe {
+            let state = arch::enter_critical();
+            let kill = try_kill(&task);
+            arch::exit_critical(state);
+
+            kill
+        };
+
+        match kill {
+            Kill::AlreadyDead => return false,
+            Kill::Busy => fe_osi::sleep(0),
+            kill => break kill,
+        };
+    };
+
+    match kill {
+        Kill::OnSyscallExit => {
+            //Get the task running again so it can leave the kernel
+            wait_queue::wake_task(&task);
+            loop {
+                match task.state.try_get() {
+                    Some(TaskState::Suspended) if task.state.try_replace(TaskState::Runnable) => {
+                        break
+                    }
+                    Some(TaskState::Suspended) | None => fe_osi::sleep(0),
+                    Some(_) => break,
+                };
+            }
+        }
+        _ => {
+            wait_queue::cancel(&task);
+            release_task(&task);
+            reap(pid);
+        }
+    }
+
+    true
+}
+
+//Returns how long the task with the given pid has spent running, or how
+//long the CPU has been idle if the pid is IDLE_PID
+pub(crate) fn cpu_usage(pid: usize) -> Option<CpuUsage> {
+    if pid == IDLE_PID {
+        Some(cpu_usage_of(&IDLE_USAGE))
+    } else {
+        get_task(pid).map(|task| task.cpu_usage())
+    }
+}
+
+fn cpu_usage_of(usage: &UsageCounter) -> CpuUsage {
+    let window_len = USAGE_WINDOW_LEN.load(Ordering::SeqCst).max(1);
+
+    CpuUsage {
+        ticks: usage.total(),
+        percent: usage.last_window() * 100 / window_len,
+    }
+}
+
+//Starts a new CPU usage window once the current one is long enough
+fn update_cpu_usage() {
+    let now = get_ticks();
+
+    unsafe {
+        if now - USAGE_WINDOW_START < CPU_USAGE_WINDOW {
+            return;
+        }
+
+        TASK_LIST_LOCK.with_lock(|| {
+            for task in TASK_LIST.values() {
+                task.usage.end_window();
+            }
+        });
+        IDLE_USAGE.end_window();
+
+        USAGE_WINDOW_LEN.store((now - USAGE_WINDOW_START) as usize, Ordering::SeqCst);
+        USAGE_WINDOW_START = now;
+    }
+}
+
+fn is_kernel_task(pid: usize) -> bool {
+    unsafe { KERNEL_TASK.as_ref() }.map(|task| task.pid) == Some(pid)
+}
+
+//Returns a snapshot of every task in the task list
+pub(crate) fn task_list() -> Vec<TaskInfo> {
+    let mut list = Vec::new();
+
+    unsafe {
+        TASK_LIST_LOCK.with_lock(|| {
+            list = TASK_LIST.values().map(|task| task.info()).collect();
+        });
+    }
+
+    list
+}
+
+//Lets the scheduler know that the priority of a task has changed
+fn priority_changed(pid: usize) {
+    unsafe {
+        SCHEDULER_LOCK.with_lock(|| {
+            if let Some(scheduler) = SCHEDULER.as_mut() {
+                scheduler.priority_changed(pid);
+            }
+        });
+    }
+}
+
+//Returns the task with the given pid if it hasn't been cleaned up yet
+pub(crate) fn get_task(pid: usize) -> Option<Arc<Task>> {
+    let mut task = None;
+
+    unsafe {
+        TASK_LIST_LOCK.with_lock(|| {
+            task = TASK_LIST.get(&pid).map(Arc::clone);
+        });
+    }
+
+    task
+}
+
 //This function is called by the task spawn syscall.
 //This function handles cleaning up after a task when
 //it returns
-pub(crate) fn new_task_helper(task_info: Box<NewTaskInfo>) -> ! {
-    let task_param: &u32 = unsafe { &*task_info.param };
-    let task_ep: fn(&u32) = unsafe { core::mem::transmute(task_info.ep) };
+pub(crate) fn new_task_helper() -> ! {
     let task = unsafe { get_cur_task_mut() };
+    let entry = task.task_info.as_mut().and_then(|info| info.entry.take());
 
-    task.task_info = Some(task_info);
-
-    task_ep(task_param);
+    //Calling the closure consumes it, so anything it captured is dropped
+    //with its real type when it returns
+    if let Some(entry) = entry {
+        entry();
+    }
 
     unsafe {
         fe_osi::exit();
@@ -274,19 +1062,73 @@ pub(crate) fn new_task_helper(task_info: Box<NewTaskInfo>) -> ! {
     loop {}
 }
 
+//Stops the running task for good and queues it to be cleaned up
 pub(crate) unsafe fn remove_task() -> bool {
-    let ret_val = get_cur_task().state.try_set(TaskState::Zombie);
+    let cur_task = get_cur_task();
+
+    //Queueing the task may allocate, which may sleep, so it's done while the
+    //task can still run
+    reap(cur_task.pid);
+    let ret_val = cur_task.state.try_set(TaskState::Zombie);
     do_context_switch();
 
     ret_val
 }
 
+//Queues a task that has exited or been killed to be cleaned up by the kernel
+fn reap(pid: usize) {
+    REAP_QUEUE.push(pid);
+    KERNEL_SEM.give();
+}
+
+#[cfg(feature = "round_robin")]
+type DefaultScheduler = RoundRobin;
+#[cfg(all(feature = "edf", not(feature = "round_robin")))]
+type DefaultScheduler = EarliestDeadlineFirst;
+#[cfg(not(any(feature = "round_robin", feature = "edf")))]
+type DefaultScheduler = PriorityScheduler;
+
 /// Starts the FeRTOS scheduler to begin executing tasks.
 ///
-/// trigger_context_switch is a pointer to a function that forces a context switch
-/// enable_systic is a closure that enables the systick interrupt
-/// reload_val is the number of counts on the systick counter in a tick
-pub fn start_scheduler<F: FnOnce(usize)>(enable_systick: F, reload_val: usize) {
+/// The PriorityScheduler is used unless the `round_robin` or `edf` feature
+/// is enabled.
+///
+/// config sets how fast the tick runs. The tick timer is set up from it.
+///
+/// # Panics
+/// Panics if the tick timer can't run at the tick rate in config.
+///
+/// # Examples
+/// ```
+/// fe_rtos::task::start_scheduler(TickConfig::new(80_000_000, 1000));
+/// ```
+pub fn start_scheduler(config: TickConfig) {
+    start_scheduler_with(DefaultScheduler::new(), config);
+}
+
+/// Starts the FeRTOS scheduler to begin executing tasks using the given
+/// scheduling policy.
+///
+/// scheduler decides which task runs next
+/// config sets how fast the tick runs. The tick timer is set up from it.
+///
+/// # Panics
+/// Panics if the tick timer can't run at the tick rate in config.
+///
+/// # Examples
+/// ```
+/// let config = TickConfig::new(80_000_000, 1000);
+/// fe_rtos::task::start_scheduler_with(RoundRobin::new(), config);
+/// ```
+pub fn start_scheduler_with<S: Scheduler + 'static>(scheduler: S, config: TickConfig) {
+    let (reload_val, tick_hz) = match (config.reload_val(), config.actual_tick_hz()) {
+        (Ok(reload_val), Ok(tick_hz)) => (reload_val, tick_hz),
+        (Err(msg), _) | (_, Err(msg)) => panic!("{}", msg),
+    };
+    TICK_HZ.store(tick_hz, Ordering::SeqCst);
+    unsafe {
+        SCHEDULER = Some(Box::new(scheduler));
+    }
     syscall::link_syscalls();
 
     unsafe {
@@ -295,40 +1137,45 @@ pub fn start_scheduler<F: FnOnce(usize)>(enable_systick: F, reload_val: usize) {
             DEFAULT_STACK_SIZE,
             kernel as *const usize,
             None,
+            TaskOptions {
+                name: Some(String::from("kernel")),
+                priority: MAX_PRIORITY,
+                time_slice: DEFAULT_TIME_SLICE,
+                suspended: false,
+                period: 0,
+                deadline: 0,
+                task_info: None,
+            },
         );
         //Add something to the scheduler queue so something can run right away
         KERNEL_TASK = Some(Arc::clone(&kernel_task_ref));
     }
 
-    enable_systick(reload_val);
+    arch::enable_systick(reload_val);
 
     loop {}
 }
 
 fn kernel(_: &mut u32) {
     let mut first_push = true;
-    let mut task_list: LinkedList<Arc<Task>> = LinkedList::new();
 
     loop {
-        let mut task_num: usize = 0;
-        let mut deleted_task_num: usize = 0;
-        let mut delete_task = false;
-
         //Make sure the kernel gets added to the scheduler when it starts
         if first_push {
             unsafe {
                 arch::disable_interrupts();
             }
         }
-        //Add all new tasks to the task list
+        //Give all new tasks to the scheduler
         while !NEW_TASK_QUEUE.is_empty() && !PUSHING_TASK.load(Ordering::SeqCst) {
             match NEW_TASK_QUEUE.pop() {
-                Ok(new_task) => {
-                    task_list.push_back(Arc::clone(&new_task));
-                    unsafe {
-                        SCHEDULER.add_task(new_task);
-                    }
-                }
+                Ok(new_task) => unsafe {
+                    SCHEDULER_LOCK.with_lock(|| {
+                        if let Some(scheduler) = SCHEDULER.as_mut() {
+                            scheduler.add_task(Arc::clone(&new_task));
+                        }
+                    });
+                },
                 Err(_) => {
                     break;
                 }
@@ -341,91 +1188,165 @@ fn kernel(_: &mut u32) {
             }
         }
 
-        for task in task_list.iter() {
-            let mut new_state = TaskState::Runnable;
-            let mut has_new_state = false;
-
-            //Check for stack overflow
-            if *task.canary != STACK_CANARY {
-                //We can't panic because the task stacks are in the heap
-                //so if there's an overflow the heap is all screwed up
-                let mut digits: [u8; 20] = [0; 20];
-                let start_idx = fe_osi::usize_to_chars(&mut digits, task.pid);
-                fe_osi::print_msg("Stack overflow in task ");
-                fe_osi::print_msg(core::str::from_utf8(&digits[start_idx..]).unwrap());
-                fe_osi::print_msg("!\n");
-                unsafe {
-                    fe_osi::exit();
-                }
-            }
+        //The kernel's stack is static, so the heap is fine
+        if unsafe { get_cur_task() }.stack_overflowed() {
+            panic!("Kernel stack overflow");
+        }
 
-            //We want to default to Runnable because if a task is in a transition state,
-            //it should be scheduled so it can finish transitioning.
-            let task_state = task.state.try_get().unwrap_or(TaskState::Runnable);
-
-            match task_state {
-                TaskState::Runnable => {}
-                TaskState::Asleep(wake_up_ticks) => {
-                    unsafe {
-                        //If this task is asleep, see if we should wake it up
-                        if wake_up_ticks < TICKS.get() {
-                            //If we wake the task up, we can run it
-                            has_new_state = true;
-                            new_state = TaskState::Runnable;
-                        }
-                    }
-                }
-                TaskState::Blocking(sem) => {
-                    let sem_ref: &Semaphore = unsafe { &*sem };
-                    //If the semaphore this task is blocking on is available
-                    //to be taken, wake up the task so it can attempt to take it
-                    if sem_ref.is_available() {
-                        has_new_state = true;
-                        new_state = TaskState::Runnable;
-                    }
-                }
-                TaskState::Zombie => {
-                    delete_task = true;
-                    deleted_task_num = task_num;
+        //Clean up after the tasks that exited or were killed
+        while let Ok(pid) = REAP_QUEUE.pop() {
+            clean_up(pid);
+        }
+
+        //A task that overflowed its stack can't queue itself, so the
+        //scheduler only lets the kernel know that one did
+        if STACK_OVERFLOWED.swap(false, Ordering::SeqCst) {
+            while let Some(task) = find_overflowed() {
+                while !task.state.try_replace(TaskState::Zombie) {
+                    sleep(0);
                 }
+                wait_queue::cancel(&task);
+                clean_up(task.pid);
             }
+        }
 
-            if has_new_state {
-                //If this fails, the kernel will just take care of it in the next run through
-                task.state.try_set(new_state);
-            }
+        update_cpu_usage();
 
-            task_num += 1;
+        //If every task is waiting, stop the tick and let the processor sleep
+        //until one of them needs to wake up
+        #[cfg(feature = "tickless")]
+        tickless_idle();
+
+        //Wait until there is more to do or the CPU usage window ends. When no
+        //other task is runnable, the kernel runs anyway as the idle task.
+        if !KERNEL_SEM.try_take() {
+            let window_end = unsafe { USAGE_WINDOW_START } + CPU_USAGE_WINDOW;
+            block(&KERNEL_SEM, Some(window_end));
         }
+    }
+}
 
-        //Delete a task if there's a task to be deleted
-        if delete_task {
-            //If this task has been removed, remove it from the list
-            //and rust will dealloc almost eveything
-            let removed_task = task_list.remove(deleted_task_num);
-
-            //We still need to manually dealloc the parameter though
-            match &removed_task.task_info {
-                Some(info) => {
-                    if !info.param.is_null() {
-                        unsafe {
-                            core::mem::drop(Box::from_raw(info.param));
-                        }
-                    }
-                }
-                None => (),
+//Removes a task that has exited or been killed from the task list and the
+//scheduler, then frees it
+fn clean_up(pid: usize) {
+    let task = match get_task(pid) {
+        Some(task) => task,
+        None => return,
+    };
+
+    //A task queues itself before it becomes a zombie, so wait for it to finish
+    while !matches!(task.state.try_get(), Some(TaskState::Zombie)) {
+        sleep(0);
+    }
+
+    unsafe {
+        TASK_LIST_LOCK.with_lock(|| {
+            TASK_LIST.remove(&pid);
+        });
+        SCHEDULER_LOCK.with_lock(|| {
+            if let Some(scheduler) = SCHEDULER.as_mut() {
+                scheduler.remove_task(pid);
             }
+        });
+    }
 
-            unsafe {
-                SCHEDULER.remove_task(removed_task.pid);
+    //A task that overflowed its stack didn't get to clean up after
+    //itself, so do it for it
+    if task.stack_overflowed() {
+        handle_stack_overflow(&task);
+    }
+
+    //Rust will dealloc almost everything when the last reference to the task
+    //is dropped at the end of this function, outside of the locks
+}
+
+//Returns a task that overflowed its stack and hasn't been cleaned up yet
+fn find_overflowed() -> Option<Arc<Task>> {
+    let mut task = None;
+
+    unsafe {
+        TASK_LIST_LOCK.with_lock(|| {
+            task = TASK_LIST
+                .values()
+                .find(|task| !is_kernel_task(task.pid) && task.stack_overflowed())
+                .map(Arc::clone);
+        });
+    }
+
+    task
+}
+
+//Sleeps until the next time a task could wake up if no tasks are runnable,
+//then catches TICKS and the idle time up with the time that passed.
+#[cfg(feature = "tickless")]
+fn tickless_idle() {
+    unsafe {
+        arch::disable_interrupts();
+
+        if let Some(wake_tick) = next_wakeup() {
+            //A task wakes up once TICKS passes its wake up tick, and the last
+            //tick of the sleep is counted by sys_tick
+            let sleep_ticks = wake_tick.saturating_add(1) - TICKS.get();
+            if sleep_ticks > 1 {
+                let slept = arch::tickless_sleep(sleep_ticks);
+                TICKS.add(slept);
+                //The time spent asleep is idle time, so it isn't charged to
+                //the kernel at the next context switch
+                IDLE_USAGE.add(slept);
+                LAST_SWITCH_TICK += slept;
+                wait_queue::wake_sleepers(TICKS.get());
             }
         }
 
-        //Going through the loop multiple times without anything else running is
-        //useless since their states will not have changed, so do a context switch
-        unsafe {
-            do_context_switch();
-        }
+        arch::enable_interrupts();
+    }
+}
+
+//Returns the tick the first waiting task will wake up on, or None if a
+//task other than the kernel is ready to run.
+//Tasks blocked without a deadline can only be woken up by an interrupt, so if
+//every task is blocked this returns u64::MAX.
+#[cfg(feature = "tickless")]
+unsafe fn next_wakeup() -> Option<u64> {
+    let kernel_pid = KERNEL_TASK.as_ref().map_or(0, |task| task.pid);
+
+    if !NEW_TASK_QUEUE.is_empty()
+        || PUSHING_TASK.load(Ordering::SeqCst)
+        || KERNEL_SEM.is_available()
+    {
+        return None;
+    }
+
+    //Interrupts are disabled, so we can't block on the lock
+    if !TASK_LIST_LOCK.try_take() {
+        return None;
+    }
+
+    let all_waiting = TASK_LIST
+        .values()
+        .filter(|task| task.pid != kernel_pid)
+        .all(|task| {
+            !task.wait.is_woken()
+                && matches!(
+                    task.state.try_get(),
+                    Some(
+                        TaskState::Asleep
+                            | TaskState::Blocking
+                            | TaskState::WaitingForEvents
+                            | TaskState::Suspended
+                    )
+                )
+        });
+
+    TASK_LIST_LOCK.give();
+
+    if !all_waiting {
+        return None;
+    }
+
+    match wait_queue::next_wake_tick() {
+        Some(tick) if tick < TICKS.get() => None,
+        tick => Some(tick.unwrap_or(u64::MAX)),
     }
 }
 
@@ -1,24 +1,36 @@
 extern crate alloc;
 
 use crate::task::task_state::TaskState;
-use crate::task::Task;
+use crate::task::{get_ticks, Task, MAX_PRIORITY, NUM_PRIORITIES};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
-pub(crate) trait Scheduler {
+/// A scheduling policy that decides which task runs next.
+///
+/// `next` is called from the tick and context switch interrupts, so it must not
+/// block or allocate memory. The other methods are called from tasks, which
+/// may be interrupted by a call to `next` at any time.
+pub trait Scheduler {
+    /// Adds a new task to be scheduled.
     fn add_task(&mut self, new_task: Arc<Task>);
+    /// Returns the task that should run next, or None if no task can run.
     fn next(&mut self) -> Option<Arc<Task>>;
+    /// Removes the task with the given pid from the scheduler.
     fn remove_task(&mut self, pid: usize);
+    /// Called when the priority of the task with the given pid has changed.
+    fn priority_changed(&mut self, _pid: usize) {}
 }
 
-pub(crate) struct RoundRobin {
+/// A scheduler that gives every task a turn, regardless of its priority.
+pub struct RoundRobin {
     queue: Vec<Arc<Task>>,
     current_index: AtomicUsize,
     busy: AtomicBool,
 }
 
 impl RoundRobin {
+    /// Creates an empty RoundRobin scheduler.
     pub const fn new() -> Self {
         RoundRobin {
             queue: Vec::new(),
@@ -37,6 +49,12 @@ impl RoundRobin {
     }
 }
 
+impl Default for RoundRobin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Scheduler for RoundRobin {
     fn add_task(&mut self, new_task: Arc<Task>) {
         self.busy.store(true, Ordering::SeqCst);
@@ -54,25 +72,23 @@ impl Scheduler for RoundRobin {
             return None;
         }
 
-        //Loop until we find a runnable task. This is fine because the kernel
-        //should always be runnable.
-        loop {
+        //Go around the queue at most once looking for a runnable task.
+        //The kernel sleeps between ticks, so there may not be one.
+        for _ in 0..self.queue.len() {
             let idx = self.current_index.load(Ordering::SeqCst);
-            let len = self.queue.len();
-            if idx >= len {
+            if idx >= self.queue.len() {
                 self.increment_index();
                 continue;
             }
             let task = &self.queue[idx];
-            let state = task.state.try_get().unwrap_or(TaskState::Runnable);
+            self.increment_index();
 
-            if let TaskState::Runnable = state {
-                self.increment_index();
+            if task.is_runnable() {
                 return Some(Arc::clone(task));
             }
-
-            self.increment_index();
         }
+
+        None
     }
 
     fn remove_task(&mut self, pid: usize) {
@@ -86,3 +102,266 @@ impl Scheduler for RoundRobin {
         self.busy.store(false, Ordering::SeqCst);
     }
 }
+
+/// A fixed priority preemptive scheduler.
+///
+/// Each priority level has its own ready queue. The highest priority runnable
+/// task is always chosen, and tasks with the same priority are run in a round
+/// robin fashion, each running for its time slice before the next gets a turn.
+pub struct PriorityScheduler {
+    queues: [Vec<Arc<Task>>; NUM_PRIORITIES],
+    current_index: [usize; NUM_PRIORITIES],
+    //The index of the task that was last picked from each queue
+    last_index: [Option<usize>; NUM_PRIORITIES],
+    busy: AtomicBool,
+}
+
+impl PriorityScheduler {
+    /// Creates an empty PriorityScheduler.
+    pub const fn new() -> Self {
+        const EMPTY_QUEUE: Vec<Arc<Task>> = Vec::new();
+
+        PriorityScheduler {
+            queues: [EMPTY_QUEUE; NUM_PRIORITIES],
+            current_index: [0; NUM_PRIORITIES],
+            last_index: [None; NUM_PRIORITIES],
+            busy: AtomicBool::new(false),
+        }
+    }
+
+    //Returns the queue for a priority, capping priorities that are too high
+    fn queue_index(priority: usize) -> usize {
+        if priority >= NUM_PRIORITIES {
+            NUM_PRIORITIES - 1
+        } else {
+            priority
+        }
+    }
+}
+
+impl Default for PriorityScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler for PriorityScheduler {
+    fn add_task(&mut self, new_task: Arc<Task>) {
+        let level = Self::queue_index(new_task.priority());
+
+        self.busy.store(true, Ordering::SeqCst);
+        self.queues[level].push(new_task);
+        self.busy.store(false, Ordering::SeqCst);
+    }
+
+    fn next(&mut self) -> Option<Arc<Task>> {
+        //Because the scheduler function can't block, return None if something
+        //is being pushed to the queue
+        if self.busy.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        //Start from the highest priority and work our way down
+        for level in (0..NUM_PRIORITIES).rev() {
+            let queue = &self.queues[level];
+            let len = queue.len();
+
+            //Let the last task picked at this priority finish its time slice
+            if let Some(idx) = self.last_index[level] {
+                if idx < len && queue[idx].has_slice_left() && queue[idx].is_runnable() {
+                    return Some(Arc::clone(&queue[idx]));
+                }
+            }
+
+            //Round robin through the tasks of this priority
+            for _ in 0..len {
+                let idx = self.current_index[level] % len;
+                self.current_index[level] = (idx + 1) % len;
+
+                if queue[idx].is_runnable() {
+                    queue[idx].start_slice();
+                    self.last_index[level] = Some(idx);
+                    return Some(Arc::clone(&queue[idx]));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn remove_task(&mut self, pid: usize) {
+        self.busy.store(true, Ordering::SeqCst);
+        for level in 0..NUM_PRIORITIES {
+            let queue = &mut self.queues[level];
+            if let Some(idx) = queue.iter().position(|task| task.pid == pid) {
+                queue.swap_remove(idx);
+                self.last_index[level] = None;
+                break;
+            }
+        }
+        self.busy.store(false, Ordering::SeqCst);
+    }
+
+    fn priority_changed(&mut self, pid: usize) {
+        self.busy.store(true, Ordering::SeqCst);
+        for level in 0..NUM_PRIORITIES {
+            let queue = &mut self.queues[level];
+            if let Some(idx) = queue.iter().position(|task| task.pid == pid) {
+                let new_level = Self::queue_index(queue[idx].priority());
+                if new_level != level {
+                    let task = queue.swap_remove(idx);
+                    self.last_index[level] = None;
+                    self.queues[new_level].push(task);
+                }
+                break;
+            }
+        }
+        self.busy.store(false, Ordering::SeqCst);
+    }
+}
+
+//The scheduling information EarliestDeadlineFirst keeps for each task
+struct EdfEntry {
+    task: Arc<Task>,
+    //The tick the task's current job was released at
+    release: u64,
+    //Whether the task has run since its current job was released
+    ran: bool,
+    //Whether the task has finished its current job
+    done: bool,
+    //Whether a deadline miss has already been counted for the current job
+    missed: bool,
+}
+
+/// An earliest deadline first scheduler for periodic tasks.
+///
+/// A periodic task releases a job every period, which must be finished before
+/// its relative deadline passes. A job is considered finished once the task
+/// goes to sleep. The runnable task whose job has the nearest absolute deadline
+/// always runs, and every deadline that passes before a job is finished is
+/// counted as a miss for that task.
+///
+/// Tasks without a period only run when no periodic task is runnable, except
+/// for tasks with `MAX_PRIORITY`, like the kernel, which always run first.
+pub struct EarliestDeadlineFirst {
+    entries: Vec<EdfEntry>,
+    current_index: usize,
+    busy: AtomicBool,
+}
+
+impl EarliestDeadlineFirst {
+    /// Creates an empty EarliestDeadlineFirst scheduler.
+    pub const fn new() -> Self {
+        EarliestDeadlineFirst {
+            entries: Vec::new(),
+            current_index: 0,
+            busy: AtomicBool::new(false),
+        }
+    }
+
+    //Releases any new jobs of a periodic task and keeps track of its deadlines
+    fn update_entry(entry: &mut EdfEntry, now: u64) {
+        let period = entry.task.period as u64;
+        let deadline = entry.release + entry.task.deadline as u64;
+
+        if let Some(TaskState::Asleep) = entry.task.state.try_get() {
+            if entry.ran {
+                entry.done = true;
+            }
+        }
+
+        if !entry.done && !entry.missed && now > deadline {
+            entry.task.miss_deadline();
+            entry.missed = true;
+        }
+
+        if now >= entry.release + period {
+            //Skip ahead if more than one period has passed
+            let periods = (now - entry.release) / period;
+            entry.release += periods * period;
+            entry.ran = false;
+            entry.done = false;
+            entry.missed = false;
+        }
+    }
+
+    //Returns the absolute deadline used to order a task
+    fn sort_key(entry: &EdfEntry) -> u64 {
+        if entry.task.priority() >= MAX_PRIORITY {
+            0
+        } else if entry.task.period == 0 {
+            u64::MAX
+        } else {
+            entry.release + entry.task.deadline as u64
+        }
+    }
+}
+
+impl Default for EarliestDeadlineFirst {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler for EarliestDeadlineFirst {
+    fn add_task(&mut self, new_task: Arc<Task>) {
+        let entry = EdfEntry {
+            task: new_task,
+            release: get_ticks(),
+            ran: false,
+            done: false,
+            missed: false,
+        };
+
+        self.busy.store(true, Ordering::SeqCst);
+        self.entries.push(entry);
+        self.busy.store(false, Ordering::SeqCst);
+    }
+
+    fn next(&mut self) -> Option<Arc<Task>> {
+        //Because the scheduler function can't block, return None if something
+        //is being pushed to the queue
+        if self.busy.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        let now = get_ticks();
+        let len = self.entries.len();
+        let mut best: Option<(usize, u64)> = None;
+
+        //Start from a different task each time so tasks with the same
+        //deadline take turns
+        for i in 0..len {
+            let idx = (self.current_index + i) % len;
+            let entry = &mut self.entries[idx];
+
+            if entry.task.period > 0 {
+                Self::update_entry(entry, now);
+            }
+
+            let key = Self::sort_key(entry);
+            let is_better = match best {
+                Some((_, best_key)) => key < best_key,
+                None => true,
+            };
+
+            if is_better && entry.task.is_runnable() {
+                best = Some((idx, key));
+            }
+        }
+
+        best.map(|(idx, _)| {
+            self.current_index = (idx + 1) % len;
+            self.entries[idx].ran = true;
+            Arc::clone(&self.entries[idx].task)
+        })
+    }
+
+    fn remove_task(&mut self, pid: usize) {
+        self.busy.store(true, Ordering::SeqCst);
+        if let Some(idx) = self.entries.iter().position(|entry| entry.task.pid == pid) {
+            self.entries.swap_remove(idx);
+        }
+        self.busy.store(false, Ordering::SeqCst);
+    }
+}
@@ -1,15 +1,34 @@
 use core::cell::RefCell;
 use core::sync::atomic::{AtomicBool, Ordering};
-use fe_osi::semaphore::Semaphore;
+use fe_osi::task::TaskStatus;
 
 #[derive(Clone, Copy)]
 pub(crate) enum TaskState {
     Runnable,
-    Asleep(u64),
-    Blocking(*const Semaphore),
+    //Waiting in the sleep queue until its wake up tick has passed
+    Asleep,
+    //Waiting in a semaphore's queue until it is given or the optional
+    //deadline in ticks has passed
+    Blocking,
+    //Waiting in an event group's queue until the flags it needs are set or
+    //the optional deadline in ticks has passed
+    WaitingForEvents,
+    Suspended,
     Zombie,
 }
 
+impl From<TaskState> for TaskStatus {
+    fn from(state: TaskState) -> Self {
+        match state {
+            TaskState::Runnable => TaskStatus::Runnable,
+            TaskState::Asleep => TaskStatus::Asleep,
+            TaskState::Blocking | TaskState::WaitingForEvents => TaskStatus::Blocking,
+            TaskState::Suspended => TaskStatus::Suspended,
+            TaskState::Zombie => TaskStatus::Zombie,
+        }
+    }
+}
+
 pub(crate) struct TaskStateStruct {
     state: RefCell<TaskState>,
     in_use: AtomicBool,
@@ -17,8 +36,12 @@ pub(crate) struct TaskStateStruct {
 
 impl TaskStateStruct {
     pub const fn new() -> Self {
+        Self::with_state(TaskState::Runnable)
+    }
+
+    pub const fn with_state(state: TaskState) -> Self {
         TaskStateStruct {
-            state: RefCell::new(TaskState::Runnable),
+            state: RefCell::new(state),
             in_use: AtomicBool::new(false),
         }
     }
@@ -39,13 +62,7 @@ impl TaskStateStruct {
     }
 
     pub fn try_set(&self, new_state: TaskState) -> bool {
-        if self
-            .in_use
-            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
-            .is_ok()
-        {
-            self.state.replace(new_state);
-            self.in_use.store(false, Ordering::SeqCst);
+        if self.try_replace(new_state) {
             //If the task state changes from being runnable, yield to make
             //sure the task doesn't run when it shouldn't
             match new_state {
@@ -60,6 +77,23 @@ impl TaskStateStruct {
             false
         }
     }
+
+    //Sets the state without yielding. This is used when changing the state
+    //of a task other than the one that is running.
+    pub fn try_replace(&self, new_state: TaskState) -> bool {
+        if self
+            .in_use
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            self.state.replace(new_state);
+            self.in_use.store(false, Ordering::SeqCst);
+
+            true
+        } else {
+            false
+        }
+    }
 }
 
 unsafe impl Send for TaskStateStruct {}
@@ -14,14 +14,19 @@ impl TickCounter {
     }
 
     pub fn inc(&self) {
-        let old_lsb = self.lsb.fetch_add(1, Ordering::SeqCst);
+        self.add(1);
+    }
+
+    pub fn add(&self, ticks: u64) {
+        let old_lsb = self.lsb.fetch_add(ticks as u32, Ordering::SeqCst);
+        let mut carry = (ticks >> 32) as u32;
 
-        //If there was overflow, increment the lsb
-        if self.lsb.load(Ordering::SeqCst) < old_lsb {
-            let old_msb = self.msb.load(Ordering::SeqCst);
-            self.msb
-                .compare_exchange(old_msb, old_msb + 1, Ordering::SeqCst, Ordering::SeqCst)
-                .ok();
+        //If there was overflow, carry it into the msb
+        if old_lsb.checked_add(ticks as u32).is_none() {
+            carry += 1;
+        }
+        if carry != 0 {
+            self.msb.fetch_add(carry, Ordering::SeqCst);
         }
     }
 
@@ -0,0 +1,43 @@
+use crate::task::tick::TickCounter;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+//Keeps track of how many ticks a task, or the idle loop, has spent running
+pub(crate) struct UsageCounter {
+    total: TickCounter,
+    //The total when the current window started
+    window_start: AtomicUsize,
+    //The number of ticks used during the last complete window
+    last_window: AtomicUsize,
+}
+
+impl UsageCounter {
+    pub const fn new() -> Self {
+        UsageCounter {
+            total: TickCounter::new(),
+            window_start: AtomicUsize::new(0),
+            last_window: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn add(&self, ticks: u64) {
+        self.total.add(ticks);
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total.get()
+    }
+
+    pub fn last_window(&self) -> usize {
+        self.last_window.load(Ordering::SeqCst)
+    }
+
+    //Finishes the current window and starts a new one.
+    //A window is much shorter than it takes usize to overflow, so only the
+    //low bits of the total are needed.
+    pub fn end_window(&self) {
+        let total = self.total.get() as usize;
+        let start = self.window_start.swap(total, Ordering::SeqCst);
+        self.last_window
+            .store(total.wrapping_sub(start), Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,296 @@
+use crate::arch;
+use crate::task::Task;
+use core::cell::Cell;
+use core::ptr::null;
+use core::sync::atomic::{AtomicBool, Ordering};
+use fe_osi::semaphore::Semaphore;
+use fe_osi::sync::EventGroup;
+
+//The links a task uses to wait in the queue of the semaphore it's blocked on
+//and in the sleep queue. The queues are linked through the tasks themselves
+//so waking a task never allocates, since semaphores can be given from
+//interrupt handlers. They are only changed with interrupts disabled.
+pub(crate) struct WaitLinks {
+    //Set when the task is woken up. The scheduler makes the task runnable
+    //the next time it looks at it.
+    woken: AtomicBool,
+    //The semaphore that woke the task, if one did
+    woken_by: Cell<*const Semaphore>,
+    //The semaphore the task is waiting on and the next task waiting on it
+    sem: Cell<*const Semaphore>,
+    next_waiter: Cell<*const Task>,
+    //The flags the task is waiting for, and whether it needs all of them, if
+    //it's waiting on an event group. Event groups queue tasks on a semaphore.
+    events: Cell<Option<(u32, bool)>>,
+    //The tick the task wakes up after and the next task in the sleep queue
+    wake_tick: Cell<Option<u64>>,
+    next_sleeper: Cell<*const Task>,
+}
+
+impl WaitLinks {
+    pub const fn new() -> Self {
+        WaitLinks {
+            woken: AtomicBool::new(false),
+            woken_by: Cell::new(null()),
+            sem: Cell::new(null()),
+            next_waiter: Cell::new(null()),
+            events: Cell::new(None),
+            wake_tick: Cell::new(None),
+            next_sleeper: Cell::new(null()),
+        }
+    }
+
+    pub fn is_woken(&self) -> bool {
+        self.woken.load(Ordering::SeqCst)
+    }
+}
+
+//Every task waiting for a tick, ordered by the tick it wakes up after
+static mut SLEEP_QUEUE: *const Task = null();
+
+fn next_waiter(task: &Task) -> &Cell<*const Task> {
+    &task.wait.next_waiter
+}
+
+fn next_sleeper(task: &Task) -> &Cell<*const Task> {
+    &task.wait.next_sleeper
+}
+
+fn critical_section<R, F: FnOnce() -> R>(f: F) -> R {
+    unsafe {
+        let state = arch::enter_critical();
+        let ret_val = f();
+        arch::exit_critical(state);
+
+        ret_val
+    }
+}
+
+//Inserts the task into the list behind every task that goes before it and
+//returns the new head of the list
+unsafe fn insert<F: Fn(&Task) -> bool>(
+    head: *const Task,
+    task: &Task,
+    next: fn(&Task) -> &Cell<*const Task>,
+    goes_before: F,
+) -> *const Task {
+    let mut prev: *const Task = null();
+    let mut cur = head;
+
+    while !cur.is_null() && goes_before(&*cur) {
+        prev = cur;
+        cur = next(&*cur).get();
+    }
+
+    next(task).set(cur);
+    if prev.is_null() {
+        task
+    } else {
+        next(&*prev).set(task);
+        head
+    }
+}
+
+//Removes the task from the list if it's in it and returns the new head of the list
+unsafe fn remove(
+    head: *const Task,
+    task: &Task,
+    next: fn(&Task) -> &Cell<*const Task>,
+) -> *const Task {
+    let task_ptr = task as *const Task;
+    let mut new_head = head;
+
+    if head == task_ptr {
+        new_head = next(task).get();
+    } else {
+        let mut cur = head;
+        while !cur.is_null() && next(&*cur).get() != task_ptr {
+            cur = next(&*cur).get();
+        }
+
+        if !cur.is_null() {
+            next(&*cur).set(next(task).get());
+        }
+    }
+
+    next(task).set(null());
+    new_head
+}
+
+//Takes the task out of whatever queues it's in
+unsafe fn unlink(task: &Task) {
+    let sem = task.wait.sem.replace(null());
+    if !sem.is_null() {
+        let waiters = (*sem).raw_waiters();
+        let head = waiters.load(Ordering::SeqCst) as *const Task;
+        waiters.store(remove(head, task, next_waiter) as usize, Ordering::SeqCst);
+        task.wait.events.set(None);
+    }
+
+    if task.wait.wake_tick.replace(None).is_some() {
+        SLEEP_QUEUE = remove(SLEEP_QUEUE, task, next_sleeper);
+    }
+}
+
+unsafe fn wake(task: &Task) {
+    unlink(task);
+    task.wait.woken.store(true, Ordering::SeqCst);
+}
+
+unsafe fn add_sleeper(task: &Task, wake_tick: u64) {
+    task.wait.wake_tick.set(Some(wake_tick));
+    SLEEP_QUEUE = insert(SLEEP_QUEUE, task, next_sleeper, |other| {
+        other.wait.wake_tick.get() <= Some(wake_tick)
+    });
+}
+
+unsafe fn add_waiter(task: &Task, sem: &Semaphore, deadline: Option<u64>) {
+    let waiters = sem.raw_waiters();
+    let head = waiters.load(Ordering::SeqCst) as *const Task;
+    let priority = task.priority();
+    let new_head = insert(head, task, next_waiter, |other| {
+        other.priority() >= priority
+    });
+    waiters.store(new_head as usize, Ordering::SeqCst);
+    task.wait.sem.set(sem);
+
+    if let Some(tick) = deadline {
+        add_sleeper(task, tick);
+    }
+}
+
+//Queues the task up to be woken when the semaphore is given or the deadline,
+//if there is one, has passed. Higher priority tasks are woken first.
+pub(crate) fn wait_on(task: &Task, sem: &Semaphore, deadline: Option<u64>) {
+    critical_section(|| unsafe {
+        unlink(task);
+        task.wait.woken.store(false, Ordering::SeqCst);
+        task.wait.woken_by.set(null());
+        add_waiter(task, sem, deadline);
+    });
+}
+
+//Queues the task up to be woken when the flags it's waiting for are set in
+//the event group or the deadline, if there is one, has passed
+pub(crate) fn wait_for_events(
+    task: &Task,
+    group: &EventGroup,
+    mask: u32,
+    wait_for_all: bool,
+    deadline: Option<u64>,
+) {
+    critical_section(|| unsafe {
+        unlink(task);
+        task.wait.woken.store(false, Ordering::SeqCst);
+        task.wait.woken_by.set(null());
+        add_waiter(task, group.raw_waiters(), deadline);
+        task.wait.events.set(Some((mask, wait_for_all)));
+    });
+}
+
+//Queues the task up to be woken once the tick count passes wake_tick
+pub(crate) fn sleep_until(task: &Task, wake_tick: u64) {
+    critical_section(|| unsafe {
+        unlink(task);
+        task.wait.woken.store(false, Ordering::SeqCst);
+        task.wait.woken_by.set(null());
+        add_sleeper(task, wake_tick);
+    });
+}
+
+//Takes the task out of the queues without waking it up
+pub(crate) fn cancel(task: &Task) {
+    critical_section(|| unsafe {
+        unlink(task);
+    });
+}
+
+//Takes the task out of the queues and wakes it up
+pub(crate) fn wake_task(task: &Task) {
+    critical_section(|| unsafe {
+        wake(task);
+    });
+}
+
+//Takes a blocked task out of the queues because it's being suspended. If a
+//semaphore already woke the task, the next task waiting on it is woken
+//instead so the wakeup isn't lost.
+pub(crate) fn withdraw(task: &Task) {
+    critical_section(|| unsafe {
+        unlink(task);
+
+        let sem = task.wait.woken_by.replace(null());
+        if task.wait.is_woken() && !sem.is_null() {
+            wake_head(&*sem);
+        }
+    });
+}
+
+//Returns true if the task is in the sleep queue without waiting on a semaphore
+pub(crate) fn is_sleeping(task: &Task) -> bool {
+    critical_section(|| task.wait.wake_tick.get().is_some() && task.wait.sem.get().is_null())
+}
+
+unsafe fn wake_head(sem: &Semaphore) {
+    let head = sem.raw_waiters().load(Ordering::SeqCst) as *const Task;
+    if !head.is_null() {
+        wake(&*head);
+        (*head).wait.woken_by.set(sem);
+    }
+}
+
+//Wakes up the first task waiting on the semaphore
+pub(crate) fn wake_one(sem: &Semaphore) {
+    critical_section(|| unsafe {
+        wake_head(sem);
+    });
+}
+
+//Wakes up every task waiting on the semaphore
+pub(crate) fn wake_all(sem: &Semaphore) {
+    critical_section(|| unsafe {
+        while sem.raw_waiters().load(Ordering::SeqCst) != 0 {
+            wake_head(sem);
+        }
+    });
+}
+
+//Wakes up every task waiting on the event group whose flags are now set
+pub(crate) fn wake_events(group: &EventGroup) {
+    critical_section(|| unsafe {
+        let mut cur = group.raw_waiters().raw_waiters().load(Ordering::SeqCst) as *const Task;
+
+        while !cur.is_null() {
+            let task = &*cur;
+            //Waking the task unlinks it, so get the next task first
+            cur = task.wait.next_waiter.get();
+
+            if let Some((mask, wait_for_all)) = task.wait.events.get() {
+                if group.raw_is_met(mask, wait_for_all) {
+                    wake(task);
+                }
+            }
+        }
+    });
+}
+
+//Wakes up every task whose wake up tick is before now
+pub(crate) fn wake_sleepers(now: u64) {
+    critical_section(|| unsafe {
+        while !SLEEP_QUEUE.is_null() && (*SLEEP_QUEUE).wait.wake_tick.get() < Some(now) {
+            wake(&*SLEEP_QUEUE);
+        }
+    });
+}
+
+//Returns the tick the first task in the sleep queue wakes up after
+#[cfg(feature = "tickless")]
+pub(crate) fn next_wake_tick() -> Option<u64> {
+    critical_section(|| unsafe {
+        if SLEEP_QUEUE.is_null() {
+            None
+        } else {
+            (*SLEEP_QUEUE).wait.wake_tick.get()
+        }
+    })
+}
@@ -0,0 +1,226 @@
+extern crate alloc;
+
+use crate::task;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::String;
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use fe_osi::semaphore::Semaphore;
+use fe_osi::sync::RawMutex;
+use fe_osi::timer::TimerCallback;
+
+/// The priority of the task that runs timer callbacks.
+pub const TIMER_TASK_PRIORITY: usize = task::MAX_PRIORITY - 1;
+/// The stack size of the task that runs timer callbacks in words.
+pub const TIMER_TASK_STACK_SIZE: usize = task::DEFAULT_STACK_SIZE;
+
+struct SoftTimer {
+    period: u64,
+    periodic: bool,
+    //The tick the timer expires after, or None if it is stopped
+    expiry: Option<u64>,
+    //This is taken out while the callback runs so the lock isn't held
+    callback: Option<TimerCallback>,
+}
+
+pub(crate) struct TimerList {
+    timers: BTreeMap<usize, SoftTimer>,
+    //The running timers, ordered by the tick they expire after
+    running: BTreeSet<(u64, usize)>,
+}
+
+static mut TIMER_LIST_LOCK: RawMutex = RawMutex::new();
+static mut TIMER_LIST: TimerList = TimerList {
+    timers: BTreeMap::new(),
+    running: BTreeSet::new(),
+};
+//Given whenever the timers change so the timer task can work out when it
+//next needs to wake up
+static TIMER_TASK_SEM: Semaphore = Semaphore::new(0);
+static TIMER_TASK_STARTED: AtomicBool = AtomicBool::new(false);
+static TIMER_TASK_PID: AtomicUsize = AtomicUsize::new(0);
+
+impl TimerList {
+    fn create(&mut self, period: u64, periodic: bool, callback: TimerCallback) -> usize {
+        static ID: AtomicUsize = AtomicUsize::new(1);
+        let id = ID.fetch_add(1, Ordering::SeqCst);
+
+        self.timers.insert(
+            id,
+            SoftTimer {
+                period,
+                periodic,
+                expiry: None,
+                callback: Some(callback),
+            },
+        );
+
+        id
+    }
+
+    //Starts the timer's period over from now
+    fn start(&mut self, id: usize) -> bool {
+        let now = task::get_ticks();
+
+        match self.timers.get_mut(&id) {
+            Some(timer) => {
+                if let Some(expiry) = timer.expiry.take() {
+                    self.running.remove(&(expiry, id));
+                }
+
+                let expiry = now + timer.period;
+                timer.expiry = Some(expiry);
+                self.running.insert((expiry, id));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn stop(&mut self, id: usize) -> bool {
+        match self.timers.get_mut(&id) {
+            Some(timer) => {
+                if let Some(expiry) = timer.expiry.take() {
+                    self.running.remove(&(expiry, id));
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn change_period(&mut self, id: usize, period: u64) -> bool {
+        match self.timers.get_mut(&id) {
+            Some(timer) => {
+                timer.period = period;
+                self.start(id)
+            }
+            None => false,
+        }
+    }
+
+    fn delete(&mut self, id: usize) -> bool {
+        self.stop(id) && self.timers.remove(&id).is_some()
+    }
+
+    //Takes the callback of a timer that expired before now, restarting the
+    //timer if it is periodic
+    fn take_expired(&mut self, now: u64) -> Option<(usize, TimerCallback)> {
+        let (expiry, id) = match self.running.iter().next() {
+            Some(&(expiry, id)) if expiry < now => (expiry, id),
+            _ => return None,
+        };
+        self.running.remove(&(expiry, id));
+
+        let timer = self.timers.get_mut(&id)?;
+        timer.expiry = None;
+        if timer.periodic {
+            //Count from the last expiry so the timer doesn't drift
+            let next_expiry = expiry + timer.period;
+            timer.expiry = Some(next_expiry);
+            self.running.insert((next_expiry, id));
+        }
+
+        timer.callback.take().map(|callback| (id, callback))
+    }
+
+    //Gives a callback back to its timer after it has run
+    fn return_callback(&mut self, id: usize, callback: TimerCallback) {
+        //If the timer was deleted while the callback ran, the callback is dropped
+        if let Some(timer) = self.timers.get_mut(&id) {
+            timer.callback = Some(callback);
+        }
+    }
+
+    fn next_expiry(&self) -> Option<u64> {
+        self.running.iter().next().map(|&(expiry, _)| expiry)
+    }
+}
+
+fn with_timers<R, F: FnOnce(&mut TimerList) -> R>(f: F) -> R {
+    unsafe {
+        TIMER_LIST_LOCK.lock();
+        let ret_val = f(&mut TIMER_LIST);
+        TIMER_LIST_LOCK.unlock().ok();
+
+        ret_val
+    }
+}
+
+//Runs a timer operation and lets the timer task know the timers changed
+fn change_timers<F: FnOnce(&mut TimerList) -> bool>(f: F) -> bool {
+    let changed = with_timers(f);
+    if changed {
+        TIMER_TASK_SEM.give();
+    }
+
+    changed
+}
+
+//Creates a stopped timer and returns its id.
+//The timer task is started the first time a timer is created.
+pub(crate) fn create_timer(period: u64, periodic: bool, callback: TimerCallback) -> usize {
+    if !TIMER_TASK_STARTED.swap(true, Ordering::SeqCst) {
+        let timer_task = unsafe {
+            task::add_task(
+                TIMER_TASK_STACK_SIZE,
+                timer_task as *const usize,
+                null_mut(),
+                task::TaskOptions {
+                    name: Some(String::from("timer")),
+                    priority: TIMER_TASK_PRIORITY,
+                    time_slice: task::DEFAULT_TIME_SLICE,
+                    suspended: false,
+                    period: 0,
+                    deadline: 0,
+                    task_info: None,
+                },
+            )
+        };
+        TIMER_TASK_PID.store(timer_task.pid, Ordering::SeqCst);
+    }
+
+    with_timers(|timers| timers.create(period, periodic, callback))
+}
+
+//Returns true if the pid is the timer task's. The timer task takes the timer
+//list's lock outside of a syscall, so it can't be killed or suspended.
+pub(crate) fn is_timer_task(pid: usize) -> bool {
+    pid != 0 && TIMER_TASK_PID.load(Ordering::SeqCst) == pid
+}
+
+pub(crate) fn start_timer(id: usize) -> bool {
+    change_timers(|timers| timers.start(id))
+}
+
+pub(crate) fn stop_timer(id: usize) -> bool {
+    change_timers(|timers| timers.stop(id))
+}
+
+pub(crate) fn change_timer_period(id: usize, period: u64) -> bool {
+    change_timers(|timers| timers.change_period(id, period))
+}
+
+pub(crate) fn delete_timer(id: usize) -> bool {
+    change_timers(|timers| timers.delete(id))
+}
+
+//Runs the callbacks of expired timers. The task blocks until the first running
+//timer expires, so the sleep queue checks the timers on each tick for it.
+fn timer_task(_: &mut u32) {
+    loop {
+        while let Some((id, mut callback)) =
+            with_timers(|timers| timers.take_expired(task::get_ticks()))
+        {
+            //Run the callback outside of the lock so it can change timers
+            callback();
+            with_timers(|timers| timers.return_callback(id, callback));
+        }
+
+        //Wait until the next timer expires or the timers change
+        let next_expiry = with_timers(|timers| timers.next_expiry());
+        if !TIMER_TASK_SEM.try_take() {
+            task::block(&TIMER_TASK_SEM, next_expiry);
+        }
+    }
+}